@@ -18,6 +18,13 @@ select-browser=Select Browser
 # home_screen.rs
 installed-header=You have { $number } web apps installed:
 not-installed-header=You don't have any web app installed. Please, press create button and create one.
+profile-blocked=The browser sandbox does not let it use this profile
+allow-profile-access=Allow access
+move-profile-to-sandbox=Move profile
+browser-missing={ $name }: { $browser } is not installed
+browser-missing-none=No installed browser can open it
+rebind-to=Use { $browser }
+rebind-all=Use installed browsers for all
 
 # creator.rs
 web=Web
@@ -39,12 +46,52 @@ non-standard-arguments=Non-standard arguments
 navbar=Nav Bar
 isolated-profile=Isolated Profile
 private-mode=Private Mode
+isolated-profile-always=Own profile
+private-mode-always=Always private
+private-mode-unsupported=No private mode
+action-name=Action name
+add-action=Add action
+firefox-prefs=Firefox preferences
+pref-name=Preference
+pref-value=Value, empty to leave it to Firefox
+add-pref=Add preference
+extensions=Extensions
+add-extension=Add extension
+layout=Layout
+layout-minimal=Minimal
+layout-compact=Back, forward and reload
+layout-tabs=Tabs
+tint=Colors
+tint-none=Firefox colors
+tint-cosmic=COSMIC theme
+tint-site=Site color
+zoom=Zoom %
+dark-mode=Dark mode
+translate=Offer translations
+download-dir=Download folder, empty leaves it to the browser
+shared-profile=Profile
+new-profile=New shared profile name
+create-profile=Create profile
+shared-profile-settings=Preferences, extensions and window settings are those of the shared profile
+profile-users={ $name } ({ $browser }): { $apps }
+profile-unused={ $name } ({ $browser }) is not used by any web app
+description=Description
+keywords=Keywords, separated by commas
+language=Language
+add-translation=Add translation
 
 # iconpicker.rs
 icon-name-to-find=Icon name to find
 my-icons=My icons
 download=Download
 
+# importer.rs
+importer=Import from other apps
+importer-header=Web apps created by other tools:
+importer-empty=No web apps created by other tools were found.
+fallback-browser=Browser for the others
+move-profile=Move profile data
+
 # icons_installator.rs
 icons-installer-header=Please wait. Downloading icons...
 icons-installer-message=This app requires icons to work with. In case we don't have access to your installed icons, we are installing Papirus icon pack to local directory so you can choose one icon for your web app from this pack.
@@ -57,3 +104,4 @@ warning=You don't meet requirements
     .app-url=  - You must provide valid URL starting with http:// or https://
     .app-icon=  - You must select an Icon for your launcher
     .app-browser=  - Please select a browser. Make sure at least one is installed system-wide or via Flatpak
+    .outdated-browser=  - The selected browser is too old for some of the options, please update it
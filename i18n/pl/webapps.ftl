@@ -18,6 +18,13 @@ select-browser=Wybierz przeglądarke
 # home_screen.rs
 installed-header=Posiadasz { $number } zainstalowanych Web Appek:
 not-installed-header=Nie posiadasz żadnych Web Appek zainstalowanych. Prosimy nacisnąć przycisk u góry i stworzyć jakąś.
+profile-blocked=Piaskownica przeglądarki nie pozwala jej używać tego profilu
+allow-profile-access=Zezwól na dostęp
+move-profile-to-sandbox=Przenieś profil
+browser-missing={ $name }: przeglądarka { $browser } nie jest zainstalowana
+browser-missing-none=Żadna zainstalowana przeglądarka nie może jej otworzyć
+rebind-to=Użyj { $browser }
+rebind-all=Użyj zainstalowanych przeglądarek dla wszystkich
 
 # creator.rs
 web=Sieć
@@ -39,12 +46,52 @@ non-standard-arguments=Niestandardowe argumenty
 navbar=Pasek nawigacyjny
 isolated-profile=Izolowany profil
 private-mode=Tryb prywatny
+isolated-profile-always=Własny profil
+private-mode-always=Zawsze prywatny
+private-mode-unsupported=Brak trybu prywatnego
+action-name=Nazwa akcji
+add-action=Dodaj akcję
+firefox-prefs=Ustawienia Firefoksa
+pref-name=Ustawienie
+pref-value=Wartość, pusta zostawia ją Firefoksowi
+add-pref=Dodaj ustawienie
+extensions=Rozszerzenia
+add-extension=Dodaj rozszerzenie
+layout=Układ
+layout-minimal=Minimalny
+layout-compact=Wstecz, dalej i odśwież
+layout-tabs=Karty
+tint=Kolory
+tint-none=Kolory Firefoksa
+tint-cosmic=Motyw COSMIC
+tint-site=Kolor strony
+zoom=Powiększenie %
+dark-mode=Tryb ciemny
+translate=Proponuj tłumaczenie
+download-dir=Folder pobierania, pusty zostawia go przeglądarce
+shared-profile=Profil
+new-profile=Nazwa nowego wspólnego profilu
+create-profile=Utwórz profil
+shared-profile-settings=Ustawienia, rozszerzenia i wygląd okna pochodzą ze wspólnego profilu
+profile-users={ $name } ({ $browser }): { $apps }
+profile-unused={ $name } ({ $browser }) nie jest używany przez żadną aplikację
+description=Opis
+keywords=Słowa kluczowe, oddzielone przecinkami
+language=Język
+add-translation=Dodaj tłumaczenie
 
 # iconpicker.rs
 icon-name-to-find=Nazwa ikony do znalezienia
 my-icons=Moje ikony
 download=Pobierz
 
+# importer.rs
+importer=Importuj z innych aplikacji
+importer-header=Aplikacje webowe utworzone przez inne narzędzia:
+importer-empty=Nie znaleziono aplikacji webowych utworzonych przez inne narzędzia.
+fallback-browser=Przeglądarka dla pozostałych
+move-profile=Przenieś dane profilu
+
 # icons_installator.rs
 icons-installer-header=Proszę czekać. Trwa pobieranie ikon...
 icons-installer-message=Ta aplikacja potrzebuje ikon do działania. W przypadku braku dostępu do ikon systemowych, zainstalujemy lokalnie pakiet ikon Papirus.
@@ -57,3 +104,4 @@ warning=Proszę spełnić poniższe wymagania
     .app-url=  - Proszę podać poprawny link zaczynający się od http:// lub https://
     .app-icon=  - Musisz wybrać ikonę aplikacji
     .app-browser=  - Proszę wybrać przeglądarke. Upewnij się, że chociaż jedna jest zainstalowana systemowo, bądź poprzez Flatpak
+    .outdated-browser=  - Wybrana przeglądarka jest zbyt stara dla niektórych opcji, zaktualizuj ją
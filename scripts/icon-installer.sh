@@ -24,7 +24,7 @@ cat <<- EOF
 
 EOF
 
-: "${LOCAL_DESTDIR:=$HOME/.local/share/icons}"
+: "${LOCAL_DESTDIR:=${XDG_DATA_HOME:-$HOME/.local/share}/icons}"
 #: "${FLATPAK_DESTDIR:=$HOME/.var/app/io.github.elevenhsoft.WebApps/data/icons}"
 : "${EXTRA_THEMES=ePapirus ePapirus-Dark Papirus-Dark Papirus-Light}"
 : "${TAG:=master}"
@@ -55,7 +55,7 @@ install() {
 cleanup() {
     echo "Clearing cache ..."
     rm -rf "$temp_file" "$temp_dir"
-    rm -f "$HOME/.cache/icon-cache.kcache"
+    rm -f "${XDG_CACHE_HOME:-$HOME/.cache}/icon-cache.kcache"
     echo "Done!"
 }
 
@@ -0,0 +1,321 @@
+use std::{
+    fs::{self, create_dir_all},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chromium::ChromiumSettings,
+    common::{
+        get_supported_browsers, my_icons_location, write_atomic, Browser, WebAppAction,
+        WebAppLauncher, WebAppTranslation,
+    },
+    prefs::PrefOverride,
+    userchrome::{Layout, Tint},
+};
+
+pub const BUNDLE_EXTENSION: &str = "webapp";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebAppBundle {
+    pub version: u32,
+    pub name: String,
+    pub url: String,
+    pub category: String,
+    pub browser: String,
+    pub browser_family: String,
+    pub navbar: bool,
+    pub isolated: bool,
+    pub private: bool,
+    pub custom_parameters: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub translations: Vec<WebAppTranslation>,
+    #[serde(default)]
+    pub actions: Vec<WebAppAction>,
+    #[serde(default)]
+    pub prefs: Vec<PrefOverride>,
+    #[serde(default)]
+    pub layout: String,
+    #[serde(default)]
+    pub tint: String,
+    #[serde(default)]
+    pub chromium: ChromiumSettings,
+    pub icon: String,
+    pub icon_data: Option<String>,
+}
+
+impl WebAppBundle {
+    pub fn from_launcher(launcher: &WebAppLauncher) -> Self {
+        let icon_path = PathBuf::from(&launcher.icon);
+
+        // icons from the theme are kept by name, files travel inside the bundle
+        let (icon, icon_data) = match fs::read(&icon_path) {
+            Ok(data) => (
+                icon_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                Some(BASE64_STANDARD.encode(data)),
+            ),
+            Err(_) => (launcher.icon.clone(), None),
+        };
+
+        Self {
+            version: BUNDLE_VERSION,
+            name: launcher.name.clone(),
+            url: launcher.url.clone(),
+            category: launcher.category.clone(),
+            browser: launcher.web_browser.name.clone(),
+            browser_family: launcher.web_browser._type.family().to_string(),
+            navbar: launcher.navbar,
+            isolated: launcher.isolate_profile,
+            private: launcher.is_incognito,
+            custom_parameters: launcher.custom_parameters.clone(),
+            description: launcher.description.clone(),
+            keywords: launcher.keywords.clone(),
+            translations: launcher.translations.clone(),
+            actions: launcher.actions.clone(),
+            prefs: launcher.prefs.clone(),
+            layout: launcher.layout.to_string(),
+            tint: launcher.tint.to_string(),
+            chromium: launcher.chromium.clone(),
+            icon,
+            icon_data,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let bundle: WebAppBundle = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        if bundle.version > BUNDLE_VERSION {
+            return Err(anyhow!(
+                "Bundle version {} is newer than supported version {}.",
+                bundle.version,
+                BUNDLE_VERSION
+            ));
+        }
+
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Installed browser with the same name, or any browser of the same engine family.
+    pub fn matching_browser(&self) -> Option<Browser> {
+        let installed: Vec<Browser> = get_supported_browsers()
+            .into_iter()
+            .filter(|browser| browser.is_installed())
+            .filter(|browser| browser._type.family() == self.browser_family)
+            .collect();
+
+        installed
+            .iter()
+            .find(|browser| browser.name == self.browser)
+            .or_else(|| installed.first())
+            .cloned()
+    }
+
+    fn install_icon(&self) -> Result<String> {
+        self.install_icon_into(&my_icons_location())
+    }
+
+    fn install_icon_into(&self, dir: &Path) -> Result<String> {
+        let Some(data) = &self.icon_data else {
+            return Ok(self.icon.clone());
+        };
+
+        let data = BASE64_STANDARD.decode(data)?;
+        create_dir_all(dir)?;
+
+        let file = Path::new(&self.icon);
+        let stem = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("icon");
+        let extension = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("svg");
+
+        let mut path = dir.join(format!("{}.{}", stem, extension));
+        let mut n = 1;
+
+        while path.exists() && fs::read(&path)? != data {
+            path = dir.join(format!("{}-{}.{}", stem, n, extension));
+            n += 1;
+        }
+
+        write_atomic(&path, &data)?;
+
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    pub fn install(&self, browser: Option<Browser>) -> Result<WebAppLauncher> {
+        let browser = browser.or_else(|| self.matching_browser()).ok_or_else(|| {
+            anyhow!(
+                "No installed browser matches {} ({}).",
+                self.browser,
+                self.browser_family
+            )
+        })?;
+
+        let mut launcher = WebAppLauncher::new(
+            self.name.clone(),
+            None,
+            self.url.clone(),
+            self.install_icon()?,
+            self.category.clone(),
+            browser,
+            self.custom_parameters.clone(),
+            self.isolated,
+            self.navbar,
+            self.private,
+        );
+
+        launcher.description.clone_from(&self.description);
+        launcher.keywords.clone_from(&self.keywords);
+        launcher.translations.clone_from(&self.translations);
+        launcher.actions.clone_from(&self.actions);
+
+        // prefs mean nothing to other engines
+        if launcher.web_browser._type.family() == "firefox" {
+            launcher.prefs.clone_from(&self.prefs);
+            launcher.layout = Layout::parse(&self.layout).unwrap_or_default();
+            launcher.tint = Tint::parse(&self.tint).unwrap_or_default();
+        }
+
+        if launcher.web_browser._type.family() == "chromium" {
+            launcher.chromium.clone_from(&self.chromium);
+        }
+
+        if !launcher.is_valid {
+            return Err(anyhow!(
+                "Web App invalid. Maybe you already have this Web App?"
+            ));
+        }
+
+        launcher.create()?;
+
+        Ok(launcher)
+    }
+}
+
+pub fn export_webapp(launcher: &WebAppLauncher, path: &Path) -> Result<()> {
+    WebAppBundle::from_launcher(launcher).write(path)
+}
+
+pub fn import_webapp(path: &Path) -> Result<WebAppLauncher> {
+    WebAppBundle::read(path)?.install(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        common::{BrowserType, DesktopEntry, DESKTOP_ENTRY_GROUP},
+        paths,
+    };
+
+    use super::*;
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let dir = std::env::temp_dir().join(format!("quick-webapps-bundle-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+
+        let icon = dir.join("mail.png");
+        fs::write(&icon, b"not really a png").unwrap();
+
+        paths::with_data_home(&dir.join("data"), || {
+            let profiles = dir.join("firefox");
+            let browser = Browser::new(
+                BrowserType::Firefox,
+                "Firefox",
+                "firefox",
+                "",
+                &profiles.to_string_lossy(),
+            );
+            let mut launcher = WebAppLauncher::new(
+                String::from("Mail"),
+                Some(String::from("mail")),
+                String::from("https://mail.example.com"),
+                icon.to_string_lossy().to_string(),
+                String::from("Office"),
+                browser,
+                String::new(),
+                true,
+                false,
+                false,
+            );
+            launcher.prefs = vec![
+                PrefOverride::parse("media.autoplay.default=5").unwrap(),
+                PrefOverride::parse("browser.cache.disk.enable=").unwrap(),
+            ];
+            launcher.layout = Layout::Tabs;
+
+            let file = dir.join(format!("mail.{}", BUNDLE_EXTENSION));
+            export_webapp(&launcher, &file).unwrap();
+            let bundle = WebAppBundle::read(&file).unwrap();
+
+            assert_eq!(bundle.name, "Mail");
+            assert_eq!(bundle.icon, "mail.png");
+
+            // another browser of the same family takes the app over
+            let profiles = dir.join("librewolf");
+            let librewolf = Browser::new(
+                BrowserType::Firefox,
+                "LibreWolf",
+                "librewolf",
+                "",
+                &profiles.to_string_lossy(),
+            );
+            let installed = bundle.install(Some(librewolf)).unwrap();
+
+            assert_eq!(installed.codename, "mail-example-com-mail");
+            assert_eq!(
+                installed.path,
+                dir.join("data/applications/webapp-mail-example-com-mail.desktop")
+            );
+
+            let entry = DesktopEntry::read(&installed.path).unwrap();
+            let entry = entry.group(DESKTOP_ENTRY_GROUP).unwrap();
+            let icon = dir.join("data/icons/QuickWebApps/mail.png");
+
+            assert_eq!(entry.get("Name").unwrap(), "Mail");
+            assert_eq!(entry.get("X-WebApp-Browser").unwrap(), "LibreWolf");
+            assert_eq!(
+                entry.get("X-WebApp-URL").unwrap(),
+                "https://mail.example.com"
+            );
+            assert_eq!(entry.get("X-WebApp-Layout").unwrap(), "tabs");
+            assert_eq!(
+                entry.get_list("X-WebApp-FirefoxPrefs"),
+                ["media.autoplay.default=5", "browser.cache.disk.enable="]
+            );
+            assert_eq!(entry.get("Icon").unwrap(), icon.to_string_lossy());
+            assert_eq!(fs::read(&icon).unwrap(), b"not really a png");
+
+            let profile = profiles.join(&installed.codename);
+            let user_js = fs::read_to_string(profile.join("user.js")).unwrap();
+
+            assert!(entry
+                .get("Exec")
+                .unwrap()
+                .contains(profile.to_string_lossy().as_ref()));
+            assert!(user_js.contains("\"media.autoplay.default\", 5"));
+        });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::common::write_atomic;
+
+// Chromium zooms in steps of 20%, a level is the number of steps
+const ZOOM_STEP: f64 = 1.2;
+
+// settings each app last wrote into the profile, so only edits are written
+// again
+const APPLIED_FILE: &str = "quick-webapps-chromium.json";
+
+/// Per-app settings written into a Chromium profile, the counterpart to the
+/// Firefox `user.js`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChromiumSettings {
+    /// Default page zoom in percent.
+    pub zoom: u32,
+    pub dark_mode: bool,
+    /// Offer to translate pages in other languages.
+    pub translate: bool,
+    /// Where downloads go without asking, left to the browser when unset.
+    pub download_dir: Option<PathBuf>,
+}
+
+impl Default for ChromiumSettings {
+    fn default() -> Self {
+        Self {
+            zoom: 100,
+            dark_mode: false,
+            translate: false,
+            download_dir: None,
+        }
+    }
+}
+
+impl ChromiumSettings {
+    fn zoom_level(&self) -> f64 {
+        (self.zoom.max(1) as f64 / 100.).ln() / ZOOM_STEP.ln()
+    }
+
+    /// Keys of `Default/Preferences`. Chromium rewrites the file itself, so
+    /// these are merged into it rather than replacing it.
+    pub fn preferences(&self) -> Value {
+        let mut preferences = json!({
+            "browser": {
+                "check_default_browser": false,
+                "has_seen_welcome_page": true,
+                // 0 follows the system, 2 is dark
+                "theme": {
+                    "color_scheme": if self.dark_mode { 2 } else { 0 },
+                    "color_scheme2": if self.dark_mode { 2 } else { 0 },
+                },
+            },
+            "partition": {
+                "default_zoom_level": { "x": self.zoom_level() },
+            },
+            "translate": { "enabled": self.translate },
+            "sync_promo": { "show_on_first_run_allowed": false },
+        });
+
+        if let Some(dir) = &self.download_dir {
+            preferences["download"] = json!({
+                "default_directory": dir,
+                "directory_upgrade": true,
+                "prompt_for_download": false,
+            });
+            preferences["savefile"] = json!({ "default_directory": dir });
+        }
+
+        preferences
+    }
+
+    pub fn local_state(&self) -> Value {
+        json!({
+            "browser": { "has_seen_welcome_page": true },
+        })
+    }
+}
+
+/// Sets every key of `patch` in `target`, going into nested objects so the
+/// rest of the browser's settings stay as they are.
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn merge_file(path: &Path, patch: &Value) -> Result<()> {
+    // a file Chromium left broken is started over
+    let mut content = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(Value::Object(Map::new()));
+
+    merge(&mut content, patch);
+
+    write_atomic(path, serde_json::to_string(&content)?.as_bytes())
+}
+
+// Keys of `patch` whose value differs from `previous`, `None` when nothing
+// changed.
+fn changes(previous: &Value, patch: &Value) -> Option<Value> {
+    let (Value::Object(previous), Value::Object(patch)) = (previous, patch) else {
+        return (previous != patch).then(|| patch.clone());
+    };
+
+    let changed: Map<String, Value> = patch
+        .iter()
+        .filter_map(|(key, value)| {
+            let changed = match previous.get(key) {
+                Some(old) => changes(old, value)?,
+                None => value.clone(),
+            };
+
+            Some((key.clone(), changed))
+        })
+        .collect();
+
+    (!changed.is_empty()).then_some(Value::Object(changed))
+}
+
+/// Writes the settings into a Chromium user data directory, skipping the
+/// first-run prompts on the next start. A new profile gets all of them,
+/// afterwards only what was edited in `app` since it last wrote them, so
+/// changes made in the browser stay and apps sharing the profile do not
+/// undo each other on every save.
+pub fn preseed_profile(profile: &Path, app: &str, settings: &ChromiumSettings) -> Result<()> {
+    let preferences = profile.join("Default/Preferences");
+    let applied_file = profile.join(APPLIED_FILE);
+    let mut applied: HashMap<String, ChromiumSettings> = fs::read_to_string(&applied_file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    if !preferences.exists() {
+        fs::create_dir_all(profile.join("Default"))?;
+
+        merge_file(&preferences, &settings.preferences())?;
+        merge_file(&profile.join("Local State"), &settings.local_state())?;
+
+        let first_run = profile.join("First Run");
+        if !first_run.exists() {
+            write_atomic(&first_run, b"")?;
+        }
+    } else if let Some(previous) = applied.get(app) {
+        if let Some(patch) = changes(&previous.preferences(), &settings.preferences()) {
+            merge_file(&preferences, &patch)?;
+        }
+    }
+
+    if applied.get(app) != Some(settings) {
+        applied.insert(app.to_string(), settings.clone());
+        write_atomic(&applied_file, serde_json::to_string(&applied)?.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_is_stored_as_level() {
+        let mut settings = ChromiumSettings::default();
+        assert_eq!(settings.zoom_level(), 0.);
+
+        settings.zoom = 144;
+        assert!((settings.zoom_level() - 2.).abs() < 1e-9);
+    }
+
+    fn read_preferences(dir: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(dir.join("Default/Preferences")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn keeps_browser_settings() {
+        let dir =
+            std::env::temp_dir().join(format!("quick-webapps-chromium-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut settings = ChromiumSettings {
+            dark_mode: true,
+            download_dir: Some(PathBuf::from("/srv/downloads")),
+            ..Default::default()
+        };
+        preseed_profile(&dir, "mail", &settings).unwrap();
+
+        let preferences = read_preferences(&dir);
+        assert_eq!(preferences["browser"]["theme"]["color_scheme2"], 2);
+        assert_eq!(preferences["translate"]["enabled"], false);
+        assert_eq!(
+            preferences["download"]["default_directory"],
+            "/srv/downloads"
+        );
+        assert!(dir.join("First Run").exists());
+        assert!(dir.join("Local State").exists());
+
+        // changed in the browser since
+        fs::write(
+            dir.join("Default/Preferences"),
+            r#"{"browser": {"window_placement": {"top": 10}, "theme": {"color_scheme2": 2}}, "translate": {"enabled": true}}"#,
+        )
+        .unwrap();
+
+        // saving the app again, or another app of a shared profile, leaves
+        // them alone
+        preseed_profile(&dir, "mail", &settings).unwrap();
+        preseed_profile(&dir, "calendar", &ChromiumSettings::default()).unwrap();
+        assert_eq!(read_preferences(&dir)["translate"]["enabled"], true);
+        assert_eq!(
+            read_preferences(&dir)["browser"]["theme"]["color_scheme2"],
+            2
+        );
+
+        // an edit only writes what was edited
+        settings.dark_mode = false;
+        preseed_profile(&dir, "mail", &settings).unwrap();
+
+        let preferences = read_preferences(&dir);
+        assert_eq!(preferences["browser"]["theme"]["color_scheme2"], 0);
+        assert_eq!(preferences["browser"]["window_placement"]["top"], 10);
+        assert_eq!(preferences["translate"]["enabled"], true);
+    }
+}
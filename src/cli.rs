@@ -0,0 +1,1221 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    bundle::{WebAppBundle, BUNDLE_EXTENSION},
+    chromium::ChromiumSettings,
+    common::{
+        get_supported_browsers, get_webapps, move_icon, parse_keywords, probe_browser_versions,
+        split_exec, Browser, BrowserType, WebAppAction, WebAppLauncher, WebAppTranslation,
+    },
+    extensions, fl,
+    foreign::{find_foreign_apps, ForeignApp},
+    migration,
+    orphan::{find_orphaned_apps, OrphanedApp},
+    prefs::PrefOverride,
+    profiles::{self, SharedProfile},
+    userchrome::{cosmic_colors, site_theme_color, Layout, Tint},
+};
+
+const USAGE: &str = "Usage: quick-webapps [COMMAND] [OPTIONS]
+
+Without a command the graphical interface is started.
+
+Commands:
+  list                     List installed web apps, including ones whose
+                           browser is missing
+  show <codename>          Show a single web app
+  create                   Create a new web app
+  edit <codename>          Change an existing web app
+  delete <codename>        Remove a web app and its profile. `restore` brings
+                           back the launcher, not the profile data
+  launch <codename>        Start a web app
+  restore <codename>       Go back to the previous version of a web app
+  export <codename>        Save a web app with its icon to a bundle file
+  import <file>            Install a web app from a bundle file
+  adopt [<file>...]        List or take over web apps made by other tools
+  browsers                 List supported browsers found on this system
+  sandbox <codename>       Check that a Flatpak browser can reach the profile
+  rebind [<codename>...]   Move web apps whose browser is missing to another
+                           browser of the same engine
+  profiles                 List shared profiles and the web apps using them
+  profiles create <name>   Create a shared profile for --browser
+  profiles delete <name>   Remove a shared profile no web app uses
+  help                     Show this message
+
+Options for create and edit:
+  --name <name>            App name
+  --url <url>              App URL
+  --icon <path|url>        Icon file or URL
+  --category <category>    Menu category
+  --browser <name>         Browser name, as printed by `browsers`
+  --parameters <args>      Non-standard browser arguments
+  --description <text>     Comment shown by launchers
+  --keywords <a,b,c>       Search keywords
+  --name-translation <locale>=<name>
+  --description-translation <locale>=<text>
+  --navbar / --no-navbar
+  --isolated / --no-isolated
+  --private / --no-private
+  --action <name>=<url>    Secondary entry point, may be repeated.
+                           On edit, replaces all existing actions
+  --pref <name>=<value>    Firefox preference, may be repeated. An empty
+                           value leaves it to the browser
+  --reset-prefs            On edit, go back to the default preferences
+  --extension <path>       Extension to install, an .xpi for Firefox or an
+                           unpacked directory for Chromium. May be repeated,
+                           on edit replaces all existing extensions
+  --no-extensions          On edit, remove all extensions
+  --layout <layout>        Firefox window without the navigation bar:
+                           minimal, compact or tabs
+  --tint <tint>            Firefox window colors: none, cosmic for the
+                           current COSMIC theme, site for the site's theme
+                           color, or a #rrggbb color
+  --zoom <percent>         Chromium default page zoom
+  --dark-mode / --no-dark-mode
+                           Chromium dark mode, or following the system
+  --translate / --no-translate
+                           Chromium offering to translate pages
+  --download-dir <dir>     Chromium download directory, empty leaves it
+                           to the browser
+  --shared-profile <name>  Use a shared profile, empty gives the web app
+                           a profile of its own. Apps on a shared profile
+                           have no preferences, extensions, layout, tint
+                           or Chromium settings of their own
+
+Options for export:
+  --output <file>          Bundle file, defaults to <codename>.webapp
+
+Options for import:
+  --browser <name>         Use this browser instead of a matching one
+
+Options for adopt:
+  --all                    Take over every app that was found
+  --browser <name>         Use this browser instead of a matching one
+  --url <url>              Use this URL when it cannot be found
+  --move-profile           Move the existing profile data along
+
+Options for rebind:
+  --all                    Rebind every web app whose browser is missing
+  --browser <name>         Browser to use, defaults to the closest match
+
+Options for sandbox:
+  --allow                  Let the browser use the profile with a user override
+  --move-profile           Move the profile into the browser's own data
+
+Global options:
+  --json                   Print machine readable output";
+
+#[derive(Debug, Default)]
+struct Options {
+    positional: Vec<String>,
+    name: Option<String>,
+    url: Option<String>,
+    icon: Option<String>,
+    category: Option<String>,
+    browser: Option<String>,
+    parameters: Option<String>,
+    description: Option<String>,
+    keywords: Option<Vec<String>>,
+    translations: Option<Vec<WebAppTranslation>>,
+    navbar: Option<bool>,
+    isolated: Option<bool>,
+    private: Option<bool>,
+    actions: Option<Vec<WebAppAction>>,
+    prefs: Option<Vec<PrefOverride>>,
+    reset_prefs: bool,
+    extensions: Option<Vec<PathBuf>>,
+    layout: Option<Layout>,
+    tint: Option<String>,
+    zoom: Option<u32>,
+    dark_mode: Option<bool>,
+    translate: Option<bool>,
+    download_dir: Option<String>,
+    shared_profile: Option<String>,
+    output: Option<String>,
+    all: bool,
+    allow: bool,
+    move_profile: bool,
+    json: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                options.positional.push(arg.clone());
+                continue;
+            }
+
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| anyhow!("{} requires a value", flag))
+            };
+
+            match flag {
+                "--name" => options.name = Some(value()?),
+                "--url" => options.url = Some(value()?),
+                "--icon" => options.icon = Some(value()?),
+                "--category" => options.category = Some(value()?),
+                "--browser" => options.browser = Some(value()?),
+                "--parameters" => options.parameters = Some(value()?),
+                "--description" => options.description = Some(value()?),
+                "--keywords" => options.keywords = Some(parse_keywords(&value()?)),
+                "--name-translation" | "--description-translation" => {
+                    let translation = value()?;
+                    let (locale, text) = translation
+                        .split_once('=')
+                        .ok_or_else(|| anyhow!("{} expects <locale>=<text>", flag))?;
+
+                    let translations = options.translations.get_or_insert_with(Vec::new);
+                    let index = match translations.iter().position(|t| t.locale == locale) {
+                        Some(index) => index,
+                        None => {
+                            translations.push(WebAppTranslation {
+                                locale: locale.to_string(),
+                                ..Default::default()
+                            });
+                            translations.len() - 1
+                        }
+                    };
+
+                    if flag == "--name-translation" {
+                        translations[index].name = text.to_string();
+                    } else {
+                        translations[index].comment = text.to_string();
+                    }
+                }
+                "--navbar" => options.navbar = Some(true),
+                "--no-navbar" => options.navbar = Some(false),
+                "--isolated" => options.isolated = Some(true),
+                "--no-isolated" => options.isolated = Some(false),
+                "--private" => options.private = Some(true),
+                "--no-private" => options.private = Some(false),
+                "--action" => {
+                    let action = value()?;
+                    let (name, url) = action
+                        .split_once('=')
+                        .ok_or_else(|| anyhow!("--action expects <name>=<url>"))?;
+
+                    options
+                        .actions
+                        .get_or_insert_with(Vec::new)
+                        .push(WebAppAction {
+                            name: name.to_string(),
+                            url: url.to_string(),
+                        });
+                }
+                "--pref" => {
+                    let pref = value()?;
+                    let pref = PrefOverride::parse(&pref)
+                        .ok_or_else(|| anyhow!("--pref expects <name>=<value>"))?;
+
+                    options.prefs.get_or_insert_with(Vec::new).push(pref);
+                }
+                "--reset-prefs" => options.reset_prefs = true,
+                "--extension" => {
+                    let path = PathBuf::from(value()?);
+                    let path = path
+                        .canonicalize()
+                        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+
+                    options.extensions.get_or_insert_with(Vec::new).push(path);
+                }
+                "--no-extensions" => options.extensions = Some(Vec::new()),
+                "--layout" => {
+                    let layout = value()?;
+                    let layout = Layout::parse(&layout)
+                        .ok_or_else(|| anyhow!("--layout expects minimal, compact or tabs"))?;
+
+                    options.layout = Some(layout);
+                }
+                "--tint" => {
+                    let tint = value()?;
+
+                    if tint != "site" && Tint::parse(&tint).is_none() {
+                        return Err(anyhow!(
+                            "--tint expects none, cosmic, site or a #rrggbb color"
+                        ));
+                    }
+
+                    options.tint = Some(tint);
+                }
+                "--zoom" => {
+                    let zoom = value()?;
+                    let zoom = zoom
+                        .trim_end_matches('%')
+                        .parse()
+                        .ok()
+                        .filter(|zoom| (25..=500).contains(zoom))
+                        .ok_or_else(|| anyhow!("--zoom expects a percentage from 25 to 500"))?;
+
+                    options.zoom = Some(zoom);
+                }
+                "--dark-mode" => options.dark_mode = Some(true),
+                "--no-dark-mode" => options.dark_mode = Some(false),
+                "--translate" => options.translate = Some(true),
+                "--no-translate" => options.translate = Some(false),
+                "--download-dir" => options.download_dir = Some(value()?),
+                "--shared-profile" => options.shared_profile = Some(value()?),
+                "--output" => options.output = Some(value()?),
+                "--all" => options.all = true,
+                "--allow" => options.allow = true,
+                "--move-profile" => options.move_profile = true,
+                "--json" => options.json = true,
+                _ => return Err(anyhow!("unknown option: {}", flag)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn codename(&self) -> Result<&str> {
+        self.positional
+            .first()
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("missing <codename> argument"))
+    }
+}
+
+// commands that write launchers bring them up to date first, the others
+// leave them alone
+const MIGRATING_COMMANDS: &[&str] = &[
+    "create", "edit", "delete", "restore", "import", "adopt", "sandbox", "rebind", "profiles",
+];
+
+pub fn run(args: Vec<String>) -> i32 {
+    let (command, rest) = args.split_first().expect("command line arguments");
+
+    let writes_launchers = MIGRATING_COMMANDS.contains(&command.as_str());
+
+    // nothing has to stay responsive here, so versions are probed right away
+    if writes_launchers || command == "browsers" {
+        probe_browser_versions();
+    }
+
+    if writes_launchers {
+        migration::run();
+    }
+
+    let result = Options::parse(rest).and_then(|options| match command.as_str() {
+        "list" => list(&options),
+        "show" => show(&options),
+        "create" => create(&options),
+        "edit" => edit(&options),
+        "delete" => delete(&options),
+        "launch" => launch(&options),
+        "restore" => restore(&options),
+        "export" => export(&options),
+        "import" => import(&options),
+        "adopt" => adopt(&options),
+        "browsers" => browsers(&options),
+        "sandbox" => sandbox(&options),
+        "rebind" => rebind(&options),
+        "profiles" => profiles(&options),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(anyhow!("unknown command: {}\n\n{}", command, USAGE)),
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+fn launcher_json(launcher: &WebAppLauncher) -> Value {
+    json!({
+        "codename": launcher.codename,
+        "name": launcher.name,
+        "url": launcher.url,
+        "icon": launcher.icon,
+        "category": launcher.category,
+        "browser": launcher.web_browser.name,
+        "navbar": launcher.navbar,
+        "isolated": launcher.isolate_profile,
+        "private": launcher.is_incognito,
+        "custom_parameters": launcher.custom_parameters,
+        "description": launcher.description,
+        "keywords": launcher.keywords,
+        "translations": launcher.translations,
+        "actions": launcher.actions,
+        "prefs": launcher.prefs.iter().map(PrefOverride::to_string).collect::<Vec<String>>(),
+        "extensions": launcher.extensions,
+        "layout": launcher.layout.as_str(),
+        "tint": launcher.tint.to_string(),
+        "chromium": launcher.chromium,
+        "shared_profile": launcher.shared_profile,
+        "path": launcher.path,
+    })
+}
+
+fn find_webapp(codename: &str) -> Result<WebAppLauncher> {
+    if let Some(launcher) = get_webapps()
+        .into_iter()
+        .flatten()
+        .find(|app| app.codename == codename)
+    {
+        return Ok(launcher);
+    }
+
+    match find_orphan(codename) {
+        Ok(orphan) => Err(anyhow!(
+            "browser of {} is missing: {}, see `quick-webapps rebind`",
+            codename,
+            orphan.browser_name
+        )),
+        Err(_) => Err(anyhow!("web app not found: {}", codename)),
+    }
+}
+
+fn find_orphan(codename: &str) -> Result<OrphanedApp> {
+    find_orphaned_apps()
+        .into_iter()
+        .find(|app| app.codename == codename)
+        .ok_or_else(|| anyhow!("no web app with a missing browser: {}", codename))
+}
+
+fn find_browser(name: Option<&str>) -> Result<Browser> {
+    let browser = match name {
+        Some(name) => Browser::web_browser(name.to_string())
+            .ok_or_else(|| anyhow!("browser not found: {}", name))?,
+        None => get_supported_browsers().remove(0),
+    };
+
+    match browser._type {
+        BrowserType::NoBrowser => Err(anyhow!("no supported browser installed")),
+        _ => Ok(browser),
+    }
+}
+
+// options left out are adjusted to the browser, given ones have to work
+fn check_capabilities(browser: &Browser, options: &Options) -> Result<()> {
+    let firefox_only =
+        options.prefs.is_some() || options.layout.is_some() || options.tint.is_some();
+
+    if firefox_only && browser._type.family() != "firefox" {
+        return Err(anyhow!(
+            "{} does not use Firefox preferences, layouts or tints.",
+            browser.name
+        ));
+    }
+
+    for path in options.extensions.iter().flatten() {
+        extensions::check(browser._type.family(), path)?;
+    }
+
+    let chromium_only = options.zoom.is_some()
+        || options.dark_mode.is_some()
+        || options.translate.is_some()
+        || options.download_dir.is_some();
+
+    if chromium_only && browser._type.family() != "chromium" {
+        return Err(anyhow!(
+            "{} does not use Chromium profile settings.",
+            browser.name
+        ));
+    }
+
+    browser.capabilities().check(
+        &browser.name,
+        options.navbar,
+        options.isolated,
+        options.private,
+    )
+}
+
+fn apply_chromium_settings(settings: &mut ChromiumSettings, options: &Options) {
+    if let Some(zoom) = options.zoom {
+        settings.zoom = zoom;
+    }
+    if let Some(dark_mode) = options.dark_mode {
+        settings.dark_mode = dark_mode;
+    }
+    if let Some(translate) = options.translate {
+        settings.translate = translate;
+    }
+    if let Some(dir) = &options.download_dir {
+        settings.download_dir = match dir.is_empty() {
+            true => None,
+            false => Some(PathBuf::from(dir)),
+        };
+    }
+}
+
+// an empty name gives the web app a profile of its own again
+fn resolve_shared_profile(browser: &Browser, options: &Options) -> Result<Option<String>> {
+    let Some(name) = options
+        .shared_profile
+        .as_deref()
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    if !profiles::sharing_supported(browser) {
+        return Err(anyhow!("{} cannot share profiles.", browser.name));
+    }
+
+    if options.isolated == Some(false) {
+        return Err(anyhow!(
+            "--shared-profile cannot be used with --no-isolated"
+        ));
+    }
+
+    let profile = SharedProfile::find(name)
+        .ok_or_else(|| anyhow!("profile not found: {}, see `quick-webapps profiles`", name))?;
+    profile.check(browser)?;
+
+    Ok(Some(profile.name))
+}
+
+// prefs, extensions, the window look and Chromium settings are written into
+// the profile, a shared one would end up with those of whichever app came last
+fn check_shared_settings(options: &Options) -> Result<()> {
+    let profile_settings = options.prefs.is_some()
+        || options
+            .extensions
+            .as_ref()
+            .is_some_and(|paths| !paths.is_empty())
+        || options.layout.is_some()
+        || options.tint.is_some()
+        || options.zoom.is_some()
+        || options.dark_mode.is_some()
+        || options.translate.is_some()
+        || options.download_dir.is_some();
+
+    match profile_settings {
+        true => Err(anyhow!(
+            "a web app on a shared profile cannot have its own preferences, extensions, layout, tint or Chromium settings"
+        )),
+        false => Ok(()),
+    }
+}
+
+// `site` is looked up once, the color is stored in the launcher
+fn resolve_tint(tint: &str, url: &str) -> Result<Tint> {
+    if tint == "site" {
+        let color = tokio::runtime::Runtime::new()?
+            .block_on(site_theme_color(url))
+            .ok_or_else(|| anyhow!("{} does not declare a theme color.", url))?;
+
+        return Ok(Tint::Color(color));
+    }
+
+    let tint = Tint::parse(tint).unwrap_or_default();
+
+    if tint == Tint::Cosmic && cosmic_colors().is_none() {
+        eprintln!("warning: no COSMIC theme found, the window keeps the Firefox colors");
+    }
+
+    Ok(tint)
+}
+
+fn warn_outdated(launcher: &WebAppLauncher) {
+    for (option, needed) in launcher.outdated_options() {
+        eprintln!(
+            "warning: {} is too old for {}, version {} or newer is needed",
+            launcher.web_browser.label, option, needed
+        );
+    }
+}
+
+fn warn_sandbox(launcher: &WebAppLauncher) {
+    if launcher.profile_blocked() {
+        eprintln!(
+            "warning: {} cannot reach {}, run `quick-webapps sandbox {}` to fix it",
+            launcher.web_browser.name,
+            launcher.profile_dir().display(),
+            launcher.codename
+        );
+    }
+}
+
+fn print_launcher(launcher: &WebAppLauncher, as_json: bool) {
+    if as_json {
+        println!("{:#}", launcher_json(launcher));
+    } else {
+        println!("Codename:   {}", launcher.codename);
+        println!("Name:       {}", launcher.name);
+        println!("URL:        {}", launcher.url);
+        println!("Icon:       {}", launcher.icon);
+        println!("Category:   {}", launcher.category);
+        println!("Browser:    {}", launcher.web_browser.name);
+        println!("Navbar:     {}", launcher.navbar);
+        println!("Isolated:   {}", launcher.isolate_profile);
+        println!("Private:    {}", launcher.is_incognito);
+        println!("Parameters: {}", launcher.custom_parameters);
+        println!("Comment:    {}", launcher.description);
+        println!("Keywords:   {}", launcher.keywords.join(", "));
+
+        if let Some(profile) = &launcher.shared_profile {
+            println!("Profile:    {}", profile);
+        }
+
+        for translation in launcher.translations.iter() {
+            println!(
+                "Name[{}]:   {} {}",
+                translation.locale, translation.name, translation.comment
+            );
+        }
+
+        for action in launcher.actions.iter() {
+            println!("Action:     {} {}", action.name, action.url);
+        }
+
+        for pref in launcher.prefs.iter() {
+            println!("Pref:       {}", pref);
+        }
+
+        for extension in launcher.extensions.iter() {
+            println!("Extension:  {}", extension.display());
+        }
+
+        if launcher.web_browser._type.family() == "firefox" {
+            println!("Layout:     {}", launcher.layout);
+            println!("Tint:       {}", launcher.tint);
+        }
+
+        if launcher.web_browser._type.family() == "chromium" {
+            let chromium = &launcher.chromium;
+
+            println!("Zoom:       {}%", chromium.zoom);
+            println!("Dark mode:  {}", chromium.dark_mode);
+            println!("Translate:  {}", chromium.translate);
+            if let Some(dir) = &chromium.download_dir {
+                println!("Downloads:  {}", dir.display());
+            }
+        }
+    }
+}
+
+fn orphan_json(app: &OrphanedApp) -> Value {
+    json!({
+        "codename": app.codename,
+        "name": app.name,
+        "browser": app.browser_name,
+        "family": app.family,
+        "profile": app.profile,
+        "orphaned": true,
+        "candidates": app
+            .candidates()
+            .iter()
+            .map(|browser| browser.name.clone())
+            .collect::<Vec<String>>(),
+        "path": app.path,
+    })
+}
+
+fn list(options: &Options) -> Result<()> {
+    let webapps: Vec<WebAppLauncher> = get_webapps().into_iter().flatten().collect();
+    let orphans = find_orphaned_apps();
+
+    if options.json {
+        let list: Vec<Value> = webapps
+            .iter()
+            .map(launcher_json)
+            .chain(orphans.iter().map(orphan_json))
+            .collect();
+        println!("{:#}", Value::Array(list));
+    } else {
+        for app in webapps.iter() {
+            println!(
+                "{}\t{}\t{}\t{}",
+                app.codename, app.name, app.web_browser.name, app.url
+            );
+        }
+
+        for app in orphans.iter() {
+            println!(
+                "{}\t{}\t{}\tbrowser missing",
+                app.codename, app.name, app.browser_name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn show(options: &Options) -> Result<()> {
+    let launcher = find_webapp(options.codename()?)?;
+    print_launcher(&launcher, options.json);
+
+    Ok(())
+}
+
+fn create(options: &Options) -> Result<()> {
+    let name = options
+        .name
+        .clone()
+        .ok_or_else(|| anyhow!("--name is required"))?;
+    let url = options
+        .url
+        .clone()
+        .ok_or_else(|| anyhow!("--url is required"))?;
+    let icon = options
+        .icon
+        .clone()
+        .ok_or_else(|| anyhow!("--icon is required"))?;
+    let browser = find_browser(options.browser.as_deref())?;
+    check_capabilities(&browser, options)?;
+
+    let mut launcher = WebAppLauncher::new(
+        name.clone(),
+        None,
+        url,
+        move_icon(icon, name),
+        options.category.clone().unwrap_or_else(|| fl!("web")),
+        browser,
+        options.parameters.clone().unwrap_or_default(),
+        options.isolated.unwrap_or(true),
+        options.navbar.unwrap_or(false),
+        options.private.unwrap_or(false),
+    );
+
+    launcher.description = options.description.clone().unwrap_or_default();
+    launcher.keywords = options.keywords.clone().unwrap_or_default();
+    launcher.translations = options.translations.clone().unwrap_or_default();
+    launcher.actions = options.actions.clone().unwrap_or_default();
+    launcher.prefs = options.prefs.clone().unwrap_or_default();
+    launcher.extensions = options.extensions.clone().unwrap_or_default();
+    launcher.layout = options.layout.unwrap_or_default();
+    apply_chromium_settings(&mut launcher.chromium, options);
+    if let Some(tint) = &options.tint {
+        launcher.tint = resolve_tint(tint, &launcher.url)?;
+    }
+    launcher.shared_profile = resolve_shared_profile(&launcher.web_browser, options)?;
+    if launcher.shared_profile.is_some() {
+        check_shared_settings(options)?;
+        launcher.drop_profile_settings();
+    }
+
+    if !launcher.is_valid {
+        return Err(anyhow!(
+            "web app invalid, maybe you already have this web app?"
+        ));
+    }
+
+    launcher.create()?;
+    warn_outdated(&launcher);
+    warn_sandbox(&launcher);
+    print_launcher(&launcher, options.json);
+
+    Ok(())
+}
+
+fn edit(options: &Options) -> Result<()> {
+    let launcher = find_webapp(options.codename()?)?;
+
+    let name = options.name.clone().unwrap_or(launcher.name.clone());
+    let icon = match options.icon.clone() {
+        Some(icon) => move_icon(icon, name.clone()),
+        None => launcher.icon.clone(),
+    };
+    let browser = match options.browser.as_deref() {
+        Some(browser) => find_browser(Some(browser))?,
+        None => launcher.web_browser.clone(),
+    };
+    check_capabilities(&browser, options)?;
+
+    let mut edited = WebAppLauncher::new(
+        name,
+        Some(launcher.codename.clone()),
+        options.url.clone().unwrap_or(launcher.url.clone()),
+        icon,
+        options
+            .category
+            .clone()
+            .unwrap_or(launcher.category.clone()),
+        browser,
+        options
+            .parameters
+            .clone()
+            .unwrap_or(launcher.custom_parameters.clone()),
+        options.isolated.unwrap_or(launcher.isolate_profile),
+        options.navbar.unwrap_or(launcher.navbar),
+        options.private.unwrap_or(launcher.is_incognito),
+    );
+
+    edited.description = options
+        .description
+        .clone()
+        .unwrap_or(launcher.description.clone());
+    edited.keywords = options
+        .keywords
+        .clone()
+        .unwrap_or(launcher.keywords.clone());
+    edited.translations = options
+        .translations
+        .clone()
+        .unwrap_or(launcher.translations.clone());
+    edited.actions = options.actions.clone().unwrap_or(launcher.actions.clone());
+
+    // given prefs are changed one by one, the others stay as they were
+    if !options.reset_prefs {
+        edited.prefs.clone_from(&launcher.prefs);
+    }
+    for pref in options.prefs.iter().flatten() {
+        edited.prefs.retain(|p| p.name != pref.name);
+        edited.prefs.push(pref.clone());
+    }
+
+    // kept extensions may not suit a browser of another engine
+    edited.extensions = match &options.extensions {
+        Some(paths) => paths.clone(),
+        None => launcher
+            .extensions
+            .iter()
+            .filter(|path| {
+                let check = extensions::check(edited.web_browser._type.family(), path);
+
+                if let Err(e) = &check {
+                    eprintln!("warning: dropping extension, {}", e);
+                }
+
+                check.is_ok()
+            })
+            .cloned()
+            .collect(),
+    };
+    edited.layout = options.layout.unwrap_or(launcher.layout);
+    edited.chromium = launcher.chromium.clone();
+    apply_chromium_settings(&mut edited.chromium, options);
+    edited.tint = match &options.tint {
+        Some(tint) => resolve_tint(tint, &edited.url)?,
+        None => launcher.tint,
+    };
+    edited.desktop_entry = launcher.desktop_entry.clone();
+
+    // a moved profile belongs to the browser it was moved for
+    if edited.web_browser.name == launcher.web_browser.name {
+        edited.profile_location = launcher.profile_location.clone();
+    }
+
+    // and so does a shared one
+    edited.shared_profile = match &options.shared_profile {
+        Some(_) => resolve_shared_profile(&edited.web_browser, options)?,
+        None if options.isolated == Some(false) => None,
+        None => launcher
+            .shared()
+            .and_then(|profile| match profile.check(&edited.web_browser) {
+                Ok(()) => Some(profile.name),
+                Err(e) => {
+                    eprintln!("warning: leaving the shared profile, {}", e);
+                    None
+                }
+            }),
+    };
+    if edited.shared_profile.is_some() {
+        check_shared_settings(options)?;
+        edited.isolate_profile = true;
+
+        if edited.drop_profile_settings() {
+            eprintln!(
+                "warning: dropping the preferences, extensions and window settings of {}, its profile is shared",
+                edited.codename
+            );
+        }
+    }
+
+    if !edited.is_valid {
+        return Err(anyhow!(
+            "web app invalid, maybe you already have this web app?"
+        ));
+    }
+
+    edited.replace(&launcher)?;
+    warn_outdated(&edited);
+    warn_sandbox(&edited);
+    print_launcher(&edited, options.json);
+
+    Ok(())
+}
+
+fn delete(options: &Options) -> Result<()> {
+    let launcher = find_webapp(options.codename()?)?;
+    launcher.delete()?;
+
+    if options.json {
+        println!("{:#}", json!({ "deleted": launcher.codename }));
+    }
+
+    Ok(())
+}
+
+fn launch(options: &Options) -> Result<()> {
+    let launcher = find_webapp(options.codename()?)?;
+    warn_sandbox(&launcher);
+
+    let args = split_exec(&launcher.exec);
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("web app has an empty Exec line"))?;
+
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if options.json {
+        println!(
+            "{:#}",
+            json!({ "launched": launcher.codename, "pid": child.id() })
+        );
+    }
+
+    Ok(())
+}
+
+fn restore(options: &Options) -> Result<()> {
+    let codename = options.codename()?;
+    let path = WebAppLauncher::restore(codename)?;
+
+    // a deleted app comes back, its profile data was removed with it
+    if let Some(launcher) = get_webapps()
+        .into_iter()
+        .flatten()
+        .find(|app| app.codename == codename)
+    {
+        if launcher.isolate_profile && !launcher.profile_dir().exists() {
+            eprintln!(
+                "warning: the profile of {} was removed, it starts with a new one",
+                codename
+            );
+        }
+    }
+
+    if options.json {
+        println!("{:#}", json!({ "restored": codename, "path": path }));
+    } else {
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+fn export(options: &Options) -> Result<()> {
+    let launcher = find_webapp(options.codename()?)?;
+    let output = options
+        .output
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", launcher.codename, BUNDLE_EXTENSION)));
+
+    WebAppBundle::from_launcher(&launcher).write(&output)?;
+
+    if options.json {
+        println!(
+            "{:#}",
+            json!({ "exported": launcher.codename, "path": output })
+        );
+    } else {
+        println!("{}", output.display());
+    }
+
+    Ok(())
+}
+
+fn import(options: &Options) -> Result<()> {
+    let file = options
+        .positional
+        .first()
+        .ok_or_else(|| anyhow!("missing <file> argument"))?;
+    let bundle = WebAppBundle::read(&PathBuf::from(file))?;
+
+    let browser = match options.browser.as_deref() {
+        Some(browser) => Some(find_browser(Some(browser))?),
+        None => None,
+    };
+
+    let launcher = bundle.install(browser)?;
+    print_launcher(&launcher, options.json);
+
+    Ok(())
+}
+
+fn foreign_json(app: &ForeignApp) -> Value {
+    json!({
+        "path": app.path,
+        "source": app.source.to_string(),
+        "name": app.name,
+        "url": app.url,
+        "icon": app.icon,
+        "family": app.family,
+        "browser": app.browser.as_ref().map(|browser| browser.name.clone()),
+        "profile": app.profile,
+    })
+}
+
+fn adopt(options: &Options) -> Result<()> {
+    let found = find_foreign_apps();
+
+    if options.positional.is_empty() && !options.all {
+        if options.json {
+            let list: Vec<Value> = found.iter().map(foreign_json).collect();
+            println!("{:#}", Value::Array(list));
+        } else {
+            for app in found.iter() {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    app.path.display(),
+                    app.source,
+                    app.name,
+                    app.url
+                );
+            }
+        }
+
+        return Ok(());
+    }
+
+    let selected: Vec<ForeignApp> = if options.all {
+        found
+    } else {
+        options
+            .positional
+            .iter()
+            .map(|file| {
+                ForeignApp::read(&PathBuf::from(file))?
+                    .ok_or_else(|| anyhow!("not a web app of another tool: {}", file))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    let browser = match options.browser.as_deref() {
+        Some(browser) => Some(find_browser(Some(browser))?),
+        None => None,
+    };
+
+    let mut failed = 0;
+
+    for mut app in selected {
+        if let Some(url) = &options.url {
+            app.url.clone_from(url);
+        }
+
+        match app.adopt(browser.clone(), options.move_profile) {
+            Ok(launcher) => print_launcher(&launcher, options.json),
+            Err(e) => {
+                eprintln!("{}: {}", app.path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(anyhow!("{} web apps could not be taken over", failed)),
+    }
+}
+
+fn browsers(options: &Options) -> Result<()> {
+    let browsers: Vec<Browser> = get_supported_browsers()
+        .into_iter()
+        .filter(|browser| browser.is_installed())
+        .collect();
+
+    if options.json {
+        let list: Vec<Value> = browsers
+            .iter()
+            .map(|browser| {
+                json!({
+                    "name": browser.name,
+                    "type": format!("{:?}", browser._type),
+                    "exec": browser.exec,
+                    "version": browser.version.as_ref().map(|v| v.to_string()),
+                })
+            })
+            .collect();
+        println!("{:#}", Value::Array(list));
+    } else {
+        for browser in browsers.iter() {
+            let version = browser
+                .version
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+
+            println!(
+                "{}\t{:?}\t{}\t{}",
+                browser.name, browser._type, version, browser.exec
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn sandbox(options: &Options) -> Result<()> {
+    let mut launcher = find_webapp(options.codename()?)?;
+
+    if options.allow && options.move_profile {
+        return Err(anyhow!(
+            "--allow and --move-profile cannot be used together"
+        ));
+    }
+
+    if launcher.profile_blocked() {
+        if options.allow {
+            launcher.allow_profile_access()?;
+        } else if options.move_profile {
+            launcher.move_profile_to_sandbox()?;
+        }
+    }
+
+    let profile = launcher.profile_dir();
+    let blocked = launcher.profile_blocked();
+
+    if options.json {
+        println!(
+            "{:#}",
+            json!({
+                "codename": launcher.codename,
+                "browser": launcher.web_browser.name,
+                "profile": profile,
+                "blocked": blocked,
+            })
+        );
+    } else if blocked {
+        println!(
+            "{} cannot reach {}. Use --allow or --move-profile.",
+            launcher.web_browser.name,
+            profile.display()
+        );
+    } else {
+        println!(
+            "{} can reach {}.",
+            launcher.web_browser.name,
+            profile.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn profiles(options: &Options) -> Result<()> {
+    match options.positional.first().map(String::as_str) {
+        None => {}
+        Some("create") => {
+            let name = options
+                .positional
+                .get(1)
+                .ok_or_else(|| anyhow!("missing <name> argument"))?;
+            let browser = find_browser(options.browser.as_deref())?;
+
+            SharedProfile::create(name, &browser)?;
+        }
+        Some("delete") => {
+            let name = options
+                .positional
+                .get(1)
+                .ok_or_else(|| anyhow!("missing <name> argument"))?;
+
+            SharedProfile::find(name)
+                .ok_or_else(|| anyhow!("profile not found: {}", name))?
+                .delete()?;
+        }
+        Some(action) => return Err(anyhow!("unknown profiles action: {}", action)),
+    }
+
+    let list = profiles::shared_profiles();
+
+    if options.json {
+        let list: Vec<Value> = list
+            .iter()
+            .map(|profile| {
+                json!({
+                    "name": profile.name,
+                    "browser": profile.browser,
+                    "path": profile.path,
+                    "apps": profile
+                        .users(None)
+                        .iter()
+                        .map(|app| app.codename.clone())
+                        .collect::<Vec<String>>(),
+                })
+            })
+            .collect();
+        println!("{:#}", Value::Array(list));
+    } else {
+        for profile in list.iter() {
+            let apps: Vec<String> = profile
+                .users(None)
+                .iter()
+                .map(|app| app.codename.clone())
+                .collect();
+
+            println!(
+                "{}\t{}\t{}\t{}",
+                profile.name,
+                profile.browser,
+                profile.path.display(),
+                apps.join(",")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn rebind(options: &Options) -> Result<()> {
+    let selected: Vec<OrphanedApp> = if options.all {
+        find_orphaned_apps()
+    } else if options.positional.is_empty() {
+        return Err(anyhow!("missing <codename> argument or --all"));
+    } else {
+        options
+            .positional
+            .iter()
+            .map(|codename| find_orphan(codename))
+            .collect::<Result<_>>()?
+    };
+
+    let browser = match options.browser.as_deref() {
+        Some(browser) => Some(find_browser(Some(browser))?),
+        None => None,
+    };
+
+    let mut failed = 0;
+
+    for app in selected {
+        let result = match &browser {
+            Some(browser) => app.rebind(browser),
+            None => app.rebind_to_best(),
+        };
+
+        match result {
+            Ok(launcher) => print_launcher(&launcher, options.json),
+            Err(e) => {
+                eprintln!("{}: {}", app.codename, e);
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(anyhow!("{} web apps could not be rebound", failed)),
+    }
+}
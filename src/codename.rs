@@ -0,0 +1,144 @@
+use url::Url;
+
+use crate::common::{desktop_filepath, get_supported_browsers};
+
+const MAX_SLUG_LEN: usize = 64;
+
+// lowercase ASCII letters, digits and single dashes; safe for file names,
+// profile directories and StartupWMClass
+pub fn slugify(input: &str) -> String {
+    let mut slug = String::new();
+
+    for c in input.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.truncate(MAX_SLUG_LEN);
+
+    slug.trim_matches('-').to_string()
+}
+
+fn host_slug(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match url.host_str() {
+            Some(host) => slugify(host.trim_start_matches("www.")),
+            None => String::new(),
+        },
+        Err(_) => String::new(),
+    }
+}
+
+pub fn codename_slug(name: &str, url: &str) -> String {
+    let slug = [host_slug(url), slugify(name)]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join("-");
+
+    let mut slug = slugify(&slug);
+
+    if slug.is_empty() {
+        slug = String::from("webapp");
+    }
+
+    slug
+}
+
+pub fn unique_codename(base: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let mut codename = base.to_string();
+    let mut n = 2;
+
+    while is_taken(&codename) {
+        // the counter replaces the end of a long slug to stay in the limit
+        let suffix = format!("-{}", n);
+        let base = &base[..base.len().min(MAX_SLUG_LEN - suffix.len())];
+
+        codename = format!("{}{}", base.trim_end_matches('-'), suffix);
+        n += 1;
+    }
+
+    codename
+}
+
+pub fn codename_taken(codename: &str) -> bool {
+    desktop_filepath(&format!("webapp-{}.desktop", codename)).exists()
+        || get_supported_browsers()
+            .iter()
+            .any(|browser| browser.app_profile_dir(codename).exists())
+}
+
+pub fn generate_codename(name: &str, url: &str) -> String {
+    unique_codename(&codename_slug(name, url), codename_taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_from_host_and_name() {
+        assert_eq!(
+            codename_slug("Mail", "https://www.mail.example.com/inbox"),
+            "mail-example-com-mail"
+        );
+    }
+
+    #[test]
+    fn slug_strips_path_separators_and_shell_characters() {
+        let slug = codename_slug("../My App $(rm -rf ~); `x` \"q\" 'a'", "not a url");
+
+        assert_eq!(slug, "my-app-rm-rf-x-q-a");
+        assert!(!slug.contains('/'));
+    }
+
+    #[test]
+    fn slug_drops_non_ascii() {
+        assert_eq!(codename_slug("Zażółć Gęślą", ""), "za-g-l");
+        assert_eq!(codename_slug("日本語", "https://例え.jp"), "xn-r8jz45g-jp");
+    }
+
+    #[test]
+    fn slug_never_empty() {
+        assert_eq!(codename_slug("", ""), "webapp");
+        assert_eq!(codename_slug("  ///  ", "file:///tmp"), "webapp");
+    }
+
+    #[test]
+    fn slug_is_limited_in_length() {
+        let slug = codename_slug(&"a".repeat(500), "");
+
+        assert!(slug.len() <= MAX_SLUG_LEN);
+    }
+
+    #[test]
+    fn slug_is_valid_filename_and_wm_class() {
+        let slug = codename_slug("\n\t-Weird--Name-\0", "https://EXAMPLE.org:8080");
+
+        assert_eq!(slug, "example-org-weird-name");
+        assert!(slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+        assert!(!slug.starts_with('-') && !slug.ends_with('-'));
+    }
+
+    #[test]
+    fn unique_codename_appends_counter() {
+        let taken = ["app", "app-2"];
+
+        assert_eq!(unique_codename("app", |c| taken.contains(&c)), "app-3");
+        assert_eq!(unique_codename("other", |c| taken.contains(&c)), "other");
+    }
+
+    #[test]
+    fn unique_codename_stays_in_length() {
+        let base = codename_slug(&"a".repeat(500), "");
+        let codename = unique_codename(&base, |c| c == base);
+
+        assert_eq!(codename.len(), MAX_SLUG_LEN);
+        assert!(codename.ends_with("a-2"));
+    }
+}
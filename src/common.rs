@@ -1,12 +1,14 @@
 #![allow(clippy::too_many_arguments)]
 
 use std::{
+    collections::HashSet,
     ffi::OsStr,
-    fs::{self, copy, create_dir_all, remove_dir_all, remove_file, File},
-    io::{self, BufRead, Cursor, Read, Write},
-    path::PathBuf,
+    fs::{self, create_dir_all, remove_dir_all, remove_file, File},
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, Error, Result};
@@ -16,9 +18,9 @@ use bytes::Bytes;
 use cosmic::widget;
 use image::io::Reader as ImageReader;
 use image::GenericImageView;
-use rand::{thread_rng, Rng};
 use reqwest::Client;
 use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
 use svg::node::element::Image;
 use svg::Document;
 use url::Url;
@@ -26,16 +28,35 @@ use usvg::fontdb;
 use walkdir::WalkDir;
 
 use crate::{
+    chromium::{preseed_profile, ChromiumSettings},
+    codename::generate_codename,
+    extensions::{chromium_flag, install_firefox, AUTO_DISABLE_SCOPES},
     fl,
+    foreign::move_profile_dir,
     icon_cache::IconCache,
-    pages,
-    supported_browsers::{flatpak_browsers, native_browsers, nix_browsers},
+    pages, paths,
+    prefs::{app_prefs, merge_user_js, Pref, PrefOverride, PrefValue},
+    profiles::SharedProfile,
+    sandbox::{flatpak_id, sandbox_profile_root, Flatpak},
+    supported_browsers::{
+        config_browsers, desktop_file_browsers, flatpak_browsers, path_browsers, snap_browsers,
+    },
+    userchrome::{user_chrome_css, Layout, Tint},
+    version::{
+        cached_version, outdated_options, probe_versions, strip_unknown_flags, Version,
+        WebAppOption,
+    },
 };
 
 lazy_static::lazy_static! {
     static ref ICON_CACHE: Mutex<IconCache> = Mutex::new(IconCache::new());
+    static ref BROWSERS: Mutex<Option<(Instant, Vec<Browser>)>> = Mutex::new(None);
 }
 
+// discovery walks PATH and desktop files, while the list is asked for on
+// every redraw
+const BROWSERS_TTL: Duration = Duration::from_secs(10);
+
 pub fn icon_cache_get(name: &'static str, size: u16) -> widget::icon::Icon {
     let mut icon_cache = ICON_CACHE.lock().unwrap();
     icon_cache.get(name, size)
@@ -49,36 +70,93 @@ pub fn is_svg(path: &str) -> bool {
     !url_valid(path) && PathBuf::from_str(path).unwrap().extension() == Some(OsStr::new("svg"))
 }
 
-pub fn home_dir() -> PathBuf {
-    let home = std::env::var("HOME");
+pub fn desktop_filepath(filename: &str) -> PathBuf {
+    paths::host_data_home().join("applications").join(filename)
+}
 
-    if let Ok(path) = home {
-        return PathBuf::from_str(&path).unwrap();
-    }
+pub fn icons_location() -> PathBuf {
+    paths::host_data_home().join("icons")
+}
 
-    PathBuf::new()
+pub fn system_icons_locations() -> Vec<PathBuf> {
+    paths::data_dirs()
+        .into_iter()
+        .map(|dir| dir.join("icons"))
+        .collect()
 }
 
-pub fn desktop_filepath(filename: &str) -> PathBuf {
-    let mut home = home_dir();
-    home.push(".local/share/applications");
-    home.join(filename)
+pub fn my_icons_location() -> PathBuf {
+    icons_location().join("QuickWebApps")
 }
 
-pub fn icons_location() -> PathBuf {
-    home_dir().join(".local/share/icons")
+const MAX_BACKUPS: usize = 5;
+
+pub fn backups_location() -> PathBuf {
+    paths::app_data_dir().join("backups")
 }
 
-pub fn system_fonts() -> PathBuf {
-    if let Ok(path) = PathBuf::from_str("/usr/share/icons") {
-        path
-    } else {
-        PathBuf::new()
+/// Writes to a temporary file next to `path` and renames it into place, so
+/// readers see either the old or the new content, never a partial file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow!("{:?} has no parent directory", path))?;
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("{:?} has no file name", path))?;
+    let temp_path = parent.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = remove_file(&temp_path);
     }
+
+    Ok(result?)
 }
 
-pub fn my_icons_location() -> PathBuf {
-    icons_location().join("QuickWebApps")
+/// Backups of a launcher, oldest first.
+pub fn launcher_backups(codename: &str) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = match fs::read_dir(backups_location().join(codename)) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some(OsStr::new("desktop")))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    backups.sort();
+    backups
+}
+
+pub fn backup_launcher(codename: &str, path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let backups_dir = backups_location().join(codename);
+    create_dir_all(&backups_dir)?;
+
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    write_atomic(
+        &backups_dir.join(format!("{:020}.desktop", stamp)),
+        &fs::read(path)?,
+    )?;
+
+    let mut backups = launcher_backups(codename);
+
+    while backups.len() > MAX_BACKUPS {
+        remove_file(backups.remove(0))?;
+    }
+
+    Ok(())
 }
 
 pub fn webapplauncher_is_valid(
@@ -88,21 +166,518 @@ pub fn webapplauncher_is_valid(
     name: &str,
     url: &str,
 ) -> bool {
-    let installed = get_webapps();
-
-    for app in installed.iter().flatten() {
-        if !url_valid(url)
-            || !webbrowser.is_installed()
-            || (name.is_empty() || app.name == name)
-            || icon.is_empty()
-            || (codename.is_empty() || app.codename == codename)
-            || url.is_empty()
-        {
-            return false;
+    if !url_valid(url)
+        || !webbrowser.is_installed()
+        || name.is_empty()
+        || icon.is_empty()
+        || codename.is_empty()
+        || url.is_empty()
+    {
+        return false;
+    }
+
+    // an app being edited keeps its own name and codename
+    !get_webapps()
+        .iter()
+        .flatten()
+        .filter(|app| app.codename != codename)
+        .any(|app| app.name == name)
+}
+
+pub const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+
+/// GNOME Web only runs a profile as a web app when its directory name, which
+/// also becomes the application id, starts with this.
+pub const EPIPHANY_APP_PREFIX: &str = "org.gnome.Epiphany.WebApp_";
+
+/// Schema version written to `X-WebApp-Version`; bump it together with a new
+/// step in `migration.rs` whenever the launcher format changes.
+pub const LAUNCHER_VERSION: u32 = 1;
+
+/// Launchers written before the key existed count as version 0.
+pub fn launcher_version(entry: &DesktopGroup) -> u32 {
+    entry
+        .get("X-WebApp-Version")
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DesktopLine {
+    // Comments, blank lines and lines we could not parse are kept verbatim
+    Raw(String),
+    Entry {
+        key: String,
+        locale: Option<String>,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopGroup {
+    pub name: String,
+    lines: Vec<DesktopLine>,
+}
+
+impl DesktopGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lines: Vec::new(),
+        }
+    }
+
+    fn raw(&self, key: &str, locale: Option<&str>) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            DesktopLine::Entry {
+                key: k,
+                locale: l,
+                value,
+            } if k == key && l.as_deref() == locale => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    fn set_raw(&mut self, key: &str, locale: Option<&str>, value: String) {
+        for line in self.lines.iter_mut() {
+            if let DesktopLine::Entry {
+                key: k,
+                locale: l,
+                value: v,
+            } = line
+            {
+                if k == key && l.as_deref() == locale {
+                    *v = value;
+                    return;
+                }
+            }
+        }
+
+        // keep trailing blank lines (group separators) after the new key
+        let position = self
+            .lines
+            .iter()
+            .rposition(|line| !matches!(line, DesktopLine::Raw(raw) if raw.trim().is_empty()))
+            .map_or(0, |p| p + 1);
+
+        self.lines.insert(
+            position,
+            DesktopLine::Entry {
+                key: key.to_string(),
+                locale: locale.map(str::to_string),
+                value,
+            },
+        );
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.raw(key, None).map(unescape_value)
+    }
+
+    pub fn get_localized(&self, key: &str, locale: &str) -> Option<String> {
+        locale_variants(locale)
+            .iter()
+            .find_map(|variant| self.raw(key, Some(variant)))
+            .or_else(|| self.raw(key, None))
+            .map(unescape_value)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.raw(key, None).map(|value| value.trim() == "true")
+    }
+
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.raw(key, None).map(split_list).unwrap_or_default()
+    }
+
+    pub fn localized(&self, key: &str) -> Vec<(String, String)> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                DesktopLine::Entry {
+                    key: k,
+                    locale: Some(locale),
+                    value,
+                } if k == key => Some((locale.clone(), unescape_value(value))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.set_raw(key, None, escape_value(value));
+    }
+
+    pub fn set_localized(&mut self, key: &str, locale: &str, value: &str) {
+        self.set_raw(key, Some(locale), escape_value(value));
+    }
+
+    pub fn set_bool(&mut self, key: &str, value: bool) {
+        self.set_raw(key, None, value.to_string());
+    }
+
+    pub fn set_list<S: AsRef<str>>(&mut self, key: &str, values: &[S]) {
+        let value = values
+            .iter()
+            .map(|item| format!("{};", escape_value(item.as_ref()).replace(';', "\\;")))
+            .collect::<String>();
+
+        self.set_raw(key, None, value);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.lines
+            .retain(|line| !matches!(line, DesktopLine::Entry { key: k, .. } if k == key));
+    }
+
+    pub fn remove_localized(&mut self, key: &str) {
+        self.lines.retain(
+            |line| !matches!(line, DesktopLine::Entry { key: k, locale: Some(_), .. } if k == key),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopEntry {
+    header: Vec<String>,
+    groups: Vec<DesktopGroup>,
+}
+
+impl DesktopEntry {
+    pub fn parse(content: &str) -> Self {
+        let mut entry = DesktopEntry::default();
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                entry
+                    .groups
+                    .push(DesktopGroup::new(&trimmed[1..trimmed.len() - 1]));
+                continue;
+            }
+
+            let Some(group) = entry.groups.last_mut() else {
+                entry.header.push(line.to_string());
+                continue;
+            };
+
+            let parsed = if trimmed.is_empty() || trimmed.starts_with('#') {
+                None
+            } else {
+                line.split_once('=')
+            };
+
+            match parsed {
+                Some((key, value)) => {
+                    let key = key.trim();
+                    let (key, locale) = match key.split_once('[') {
+                        Some((key, locale)) if locale.ends_with(']') => {
+                            (key, Some(locale[..locale.len() - 1].to_string()))
+                        }
+                        _ => (key, None),
+                    };
+
+                    group.lines.push(DesktopLine::Entry {
+                        key: key.to_string(),
+                        locale,
+                        value: value.trim_start().to_string(),
+                    });
+                }
+                None => {
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                        tracing::warn!("Invalid desktop entry line: {}", line);
+                    }
+                    group.lines.push(DesktopLine::Raw(line.to_string()));
+                }
+            }
+        }
+
+        entry
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_atomic(path, self.to_string().as_bytes())
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &DesktopGroup> {
+        self.groups.iter()
+    }
+
+    pub fn group(&self, name: &str) -> Option<&DesktopGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> &mut DesktopGroup {
+        match self.groups.iter().position(|group| group.name == name) {
+            Some(position) => &mut self.groups[position],
+            None => {
+                if let Some(last) = self.groups.last_mut() {
+                    if !matches!(last.lines.last(), Some(DesktopLine::Raw(raw)) if raw.trim().is_empty())
+                    {
+                        last.lines.push(DesktopLine::Raw(String::new()));
+                    }
+                }
+
+                self.groups.push(DesktopGroup::new(name));
+                self.groups.last_mut().unwrap()
+            }
+        }
+    }
+
+    pub fn remove_group(&mut self, name: &str) {
+        self.groups.retain(|group| group.name != name);
+    }
+}
+
+impl std::fmt::Display for DesktopEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.header {
+            writeln!(f, "{}", line)?;
+        }
+
+        for group in &self.groups {
+            writeln!(f, "[{}]", group.name)?;
+
+            for line in &group.lines {
+                match line {
+                    DesktopLine::Raw(raw) => writeln!(f, "{}", raw)?,
+                    DesktopLine::Entry { key, locale, value } => match locale {
+                        Some(locale) => writeln!(f, "{}[{}]={}", key, locale, value)?,
+                        None => writeln!(f, "{}={}", key, value)?,
+                    },
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn escape_value(value: &str) -> String {
+    let mut escaped = String::new();
+
+    for (n, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ' ' if n == 0 => escaped.push_str("\\s"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(';') => unescaped.push(';'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ';' => items.push(unescape_value(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        items.push(unescape_value(&current));
+    }
+
+    items
+}
+
+// lang_COUNTRY@MODIFIER, lang_COUNTRY, lang@MODIFIER, lang; most specific first
+fn locale_variants(locale: &str) -> Vec<String> {
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (base, modifier) = match locale.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (locale, None),
+    };
+    let (lang, country) = match base.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (base, None),
+    };
+
+    let mut variants = Vec::new();
+
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    variants.push(lang.to_string());
+
+    variants
+}
+
+// characters the Desktop Entry spec reserves in Exec arguments
+const EXEC_RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+/// Writes one argument the way the Exec key expects it: `%` doubled so it is
+/// not taken for a field code, and quoted when it holds reserved characters.
+pub fn quote_exec_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+
+    if !arg.is_empty() && !arg.contains(EXEC_RESERVED) {
+        return arg;
+    }
+
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
         }
+        quoted.push(c);
     }
+    quoted.push('"');
 
-    true
+    quoted
+}
+
+/// Splits an Exec value into arguments, undoing `quote_exec_arg`. Field
+/// codes such as `%u` are kept as they are.
+pub fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                current.push('%');
+                has_arg = true;
+            }
+            ' ' if !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebAppAction {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebAppTranslation {
+    pub locale: String,
+    pub name: String,
+    pub comment: String,
+}
+
+pub fn parse_keywords(input: &str) -> Vec<String> {
+    input
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|keyword| !keyword.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn action_group_name(id: &str) -> String {
+    format!("Desktop Action {}", id)
+}
+
+// action identifiers may only contain A-Za-z0-9-
+fn unique_action_id(name: &str, taken: &[String]) -> String {
+    let base: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-')
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("-");
+    let base = if base.is_empty() {
+        String::from("action")
+    } else {
+        base
+    };
+
+    let mut id = base.clone();
+    let mut n = 2;
+
+    while taken.contains(&id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+
+    id
 }
 
 #[derive(Debug, Clone)]
@@ -121,6 +696,26 @@ pub struct WebAppLauncher {
     pub isolate_profile: bool,
     pub navbar: bool,
     pub is_incognito: bool,
+    pub description: String,
+    pub keywords: Vec<String>,
+    pub translations: Vec<WebAppTranslation>,
+    pub actions: Vec<WebAppAction>,
+    /// Firefox prefs that differ from the bundled `user.js`.
+    pub prefs: Vec<PrefOverride>,
+    /// Local extension packages installed into the profile, `.xpi` files
+    /// for Firefox and unpacked directories for Chromium.
+    pub extensions: Vec<PathBuf>,
+    /// Firefox window layout while the navigation bar is off.
+    pub layout: Layout,
+    pub tint: Tint,
+    /// Settings preseeded into an isolated Chromium profile.
+    pub chromium: ChromiumSettings,
+    /// Name of a shared profile used instead of one of its own.
+    pub shared_profile: Option<String>,
+    /// Profile directory chosen instead of the browser default, e.g. after
+    /// moving it into a Flatpak sandbox.
+    pub profile_location: Option<PathBuf>,
+    pub desktop_entry: DesktopEntry,
 }
 
 impl WebAppLauncher {
@@ -136,19 +731,18 @@ impl WebAppLauncher {
         navbar: bool,
         privatewindow: bool,
     ) -> Self {
-        let codename = if let Some(codename) = codename {
-            codename
-        } else {
-            let random_code: u16 = thread_rng().gen_range(1000..10000);
-            format!("{}{}", name.replace(' ', ""), random_code)
-        };
+        let codename = codename.unwrap_or_else(|| generate_codename(&name, &url));
         let filename = format!("webapp-{}.desktop", codename);
         let path = desktop_filepath(&filename);
         let web_browser = browser;
         let exec = web_browser.exec.clone();
         let args = Vec::new();
-        let isolate_profile = isolated;
-        let is_incognito = privatewindow;
+
+        // options the engine cannot honour are dropped here, see Capabilities
+        let capabilities = web_browser.capabilities();
+        let isolate_profile = capabilities.isolated_profile.resolve(isolated);
+        let navbar = capabilities.navbar.resolve(navbar);
+        let is_incognito = capabilities.private_window.resolve(privatewindow);
 
         let is_valid = webapplauncher_is_valid(&web_browser, &icon, &codename, &name, &url);
 
@@ -167,98 +761,146 @@ impl WebAppLauncher {
             isolate_profile,
             navbar,
             is_incognito,
+            description: String::new(),
+            keywords: Vec::new(),
+            translations: Vec::new(),
+            actions: Vec::new(),
+            prefs: Vec::new(),
+            extensions: Vec::new(),
+            layout: Layout::default(),
+            tint: Tint::default(),
+            chromium: ChromiumSettings::default(),
+            shared_profile: None,
+            profile_location: None,
+            desktop_entry: DesktopEntry::default(),
         }
     }
 
     pub fn read(path: PathBuf, codename: String) -> Result<WebAppLauncher, Error> {
-        let file = File::open(&path)?;
-        let mut browser_name = String::new();
-        let mut name = String::new();
-        let mut icon = String::new();
-        let mut is_valid = false;
-        let mut exec = String::new();
-        let mut args = Vec::new();
-        let mut category = String::new();
-        let mut url = String::new();
-        let mut custom_parameters = String::new();
-        let mut isolate_profile = false;
-        let mut navbar = false;
-        let mut is_incognito = false;
-
-        let reader = io::BufReader::new(file);
-
-        let mut is_webapp = false;
-
-        for line_result in reader.lines() {
-            match line_result {
-                Ok(line) => {
-                    if line.contains("StartupWMClass=WebApp")
-                        || line.contains("StartupWMClass=Chromium")
-                        || line.contains("StartupWMClass=ICE-SSB")
-                    {
-                        is_webapp = true;
-                    };
-
-                    if line.contains("Name=") {
-                        name = line.replace("Name=", "");
-                    };
-
-                    if line.contains("Icon=") {
-                        icon = line.replace("Icon=", "");
-                    };
+        let desktop_entry = DesktopEntry::read(&path)?;
 
-                    if line.contains("Exec=") {
-                        exec = line.replace("Exec=", "");
-                    };
-
-                    if line.contains("Categories=") {
-                        category = line
-                            .replace("Categories=", "")
-                            .replace("GTK;", "")
-                            .replace(';', "");
-                    };
+        Self::from_entry(path, codename, desktop_entry)
+    }
 
-                    if line.contains("X-WebApp-Browser=") {
-                        browser_name = line.replace("X-WebApp-Browser=", "");
-                    };
+    pub fn from_entry(
+        path: PathBuf,
+        codename: String,
+        desktop_entry: DesktopEntry,
+    ) -> Result<WebAppLauncher, Error> {
+        let entry = desktop_entry
+            .group(DESKTOP_ENTRY_GROUP)
+            .ok_or_else(|| anyhow!("Missing [{}] group.", DESKTOP_ENTRY_GROUP))?;
+
+        if launcher_version(entry) > LAUNCHER_VERSION {
+            tracing::warn!(
+                "{} was written by a newer version of Quick Web Apps.",
+                path.display()
+            );
+        }
 
-                    if line.contains("X-WebApp-URL=") {
-                        url = line.replace("X-WebApp-URL=", "");
-                    };
+        let wm_class = entry.get("StartupWMClass").unwrap_or_default();
+        let is_webapp = wm_class.starts_with("WebApp")
+            || wm_class.starts_with(EPIPHANY_APP_PREFIX)
+            || wm_class.starts_with("Chromium")
+            || wm_class.starts_with("ICE-SSB");
+
+        let name = entry.get("Name").unwrap_or_default();
+        let icon = entry.get("Icon").unwrap_or_default();
+        let exec = entry.get("Exec").unwrap_or_default();
+        let category = entry
+            .get_list("Categories")
+            .into_iter()
+            .find(|category| category != "GTK")
+            .unwrap_or_default();
+        let browser_name = entry.get("X-WebApp-Browser").unwrap_or_default();
+        let url = entry.get("X-WebApp-URL").unwrap_or_default();
+        let custom_parameters = entry.get("X-WebApp-CustomParameters").unwrap_or_default();
+        let isolate_profile = entry.get_bool("X-WebApp-Isolated").unwrap_or(false);
+        let navbar = entry.get_bool("X-WebApp-Navbar").unwrap_or(false);
+        let is_incognito = entry.get_bool("X-WebApp-PrivateWindow").unwrap_or(false);
+        let profile_location = entry.get("X-WebApp-Profile").map(PathBuf::from);
+        let prefs = entry
+            .get_list("X-WebApp-FirefoxPrefs")
+            .iter()
+            .filter_map(|pref| PrefOverride::parse(pref))
+            .collect();
+        let extensions = entry
+            .get_list("X-WebApp-Extensions")
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        let layout = entry
+            .get("X-WebApp-Layout")
+            .and_then(|layout| Layout::parse(&layout))
+            .unwrap_or_default();
+        let tint = entry
+            .get("X-WebApp-Tint")
+            .and_then(|tint| Tint::parse(&tint))
+            .unwrap_or_default();
+        let defaults = ChromiumSettings::default();
+        let chromium = ChromiumSettings {
+            zoom: entry
+                .get("X-WebApp-Zoom")
+                .and_then(|zoom| zoom.parse().ok())
+                .unwrap_or(defaults.zoom),
+            dark_mode: entry
+                .get_bool("X-WebApp-DarkMode")
+                .unwrap_or(defaults.dark_mode),
+            translate: entry
+                .get_bool("X-WebApp-Translate")
+                .unwrap_or(defaults.translate),
+            download_dir: entry.get("X-WebApp-DownloadDir").map(PathBuf::from),
+        };
+        let shared_profile = entry.get("X-WebApp-SharedProfile");
 
-                    if line.contains("X-WebApp-CustomParameters=") {
-                        custom_parameters = line.replace("X-WebApp-CustomParameters=", "");
-                    };
+        let description = entry.get("Comment").unwrap_or_default();
+        let keywords = entry.get_list("Keywords");
 
-                    if line.contains("X-WebApp-Isolated=") {
-                        isolate_profile = line.replace("X-WebApp-Isolated=", "") == "true"
-                    };
+        let mut translations: Vec<WebAppTranslation> = Vec::new();
 
-                    if line.contains("X-WebApp-Navbar=") {
-                        navbar = line.replace("X-WebApp-Navbar=", "") == "true"
-                    };
+        for (locale, name) in entry.localized("Name") {
+            translations.push(WebAppTranslation {
+                locale,
+                name,
+                comment: String::new(),
+            });
+        }
 
-                    if line.contains("X-WebApp-PrivateWindow=") {
-                        is_incognito = line.replace("X-WebApp-PrivateWindow=", "") == "true"
-                    };
-                }
-                Err(e) => eprintln!("Error reading line: {}", e),
+        for (locale, comment) in entry.localized("Comment") {
+            match translations.iter_mut().find(|t| t.locale == locale) {
+                Some(translation) => translation.comment = comment,
+                None => translations.push(WebAppTranslation {
+                    locale,
+                    name: String::new(),
+                    comment,
+                }),
             }
         }
 
-        if is_webapp && !name.is_empty() && !icon.is_empty() {
-            is_valid = true
-        }
+        let is_valid = is_webapp && !name.is_empty() && !icon.is_empty();
+
+        let actions = entry
+            .get_list("Actions")
+            .iter()
+            .filter_map(|id| desktop_entry.group(&action_group_name(id)))
+            .filter_map(|group| {
+                Some(WebAppAction {
+                    name: group.get("Name")?,
+                    url: group.get("X-WebApp-URL")?,
+                })
+            })
+            .collect();
 
         let web_browser = Browser::web_browser(browser_name);
 
         match web_browser {
             Some(web_browser) => {
-                exec.split(' ').enumerate().for_each(|(n, arg)| {
-                    if n > 0 && !arg.is_empty() {
-                        args.push(arg.to_string())
-                    }
-                });
+                let args = split_exec(&exec).into_iter().skip(1).collect();
+
+                let capabilities = web_browser.capabilities();
+                let isolate_profile = capabilities.isolated_profile.resolve(isolate_profile);
+                let navbar = capabilities.navbar.resolve(navbar);
+                let is_incognito = capabilities.private_window.resolve(is_incognito);
 
                 Ok(WebAppLauncher {
                     path,
@@ -275,177 +917,539 @@ impl WebAppLauncher {
                     isolate_profile,
                     navbar,
                     is_incognito,
+                    description,
+                    keywords,
+                    translations,
+                    actions,
+                    prefs,
+                    extensions,
+                    layout,
+                    tint,
+                    chromium,
+                    shared_profile,
+                    profile_location,
+                    desktop_entry,
                 })
             }
             None => Err(anyhow!("Cannot read web app launcher.")),
         }
     }
 
-    fn create_firefox_userjs(&self, path: PathBuf) -> bool {
-        let content = include_bytes!("../data/runtime/firefox/profile/user.js");
+    // only the prefs we own are rewritten, whatever else the user put in
+    // user.js stays
+    fn create_firefox_userjs(&self, path: PathBuf) -> Result<()> {
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let (mut owned, mut prefs) = app_prefs(&self.prefs, self.web_browser.version.as_ref());
+
+        // extensions dropped into the profile stay disabled otherwise
+        if owned.insert(String::from(AUTO_DISABLE_SCOPES)) && !self.extensions.is_empty() {
+            prefs.push(Pref {
+                name: String::from(AUTO_DISABLE_SCOPES),
+                value: PrefValue::Int(0),
+            });
+        }
+
+        // with a tab strip, links meant for new windows open as tabs
+        let open_newwindow = "browser.link.open_newwindow";
+        if self.layout == Layout::Tabs
+            && !self.navbar
+            && !self.prefs.iter().any(|pref| pref.name == open_newwindow)
+        {
+            prefs.retain(|pref| pref.name != open_newwindow);
+            prefs.push(Pref {
+                name: String::from(open_newwindow),
+                value: PrefValue::Int(3),
+            });
+        }
+
+        // overrides dropped since the launcher was last written are ours too
+        if let Ok(entry) = DesktopEntry::read(&self.path) {
+            let previous = entry
+                .group(DESKTOP_ENTRY_GROUP)
+                .map(|group| group.get_list("X-WebApp-FirefoxPrefs"))
+                .unwrap_or_default();
+
+            owned.extend(
+                previous
+                    .iter()
+                    .filter_map(|pref| PrefOverride::parse(pref))
+                    .map(|pref| pref.name),
+            );
+        }
+
+        write_atomic(&path, merge_user_js(&existing, &owned, &prefs).as_bytes())
+    }
+
+    fn create_user_chrome_css(&self, path: PathBuf) -> Result<()> {
+        let css = user_chrome_css(self.layout, self.navbar, self.tint);
+
+        write_atomic(&path, css.as_bytes())
+    }
+
+    fn firefox_profile(&self, profile_path: &Path) -> Result<()> {
+        let user_chrome_css = profile_path.join("chrome");
+
+        create_dir_all(&user_chrome_css)?;
+
+        self.create_firefox_userjs(profile_path.join("user.js"))?;
+        self.create_user_chrome_css(user_chrome_css.join("userChrome.css"))?;
+
+        if let Err(e) = install_firefox(profile_path, &self.extensions) {
+            tracing::error!("Cannot install extensions of {}: {}", self.codename, e);
+        }
+
+        Ok(())
+    }
+
+    // GNOME Web names the application after the profile directory, so it
+    // has to carry the prefix and a `.app` file marking it as a web app
+    fn epiphany_profile(&self, profile_dir: &Path) -> Result<()> {
+        let app_file = profile_dir.join(".app");
+
+        if !app_file.exists() {
+            write_atomic(&app_file, b"")?;
+        }
+
+        Ok(())
+    }
+
+    fn chromium_profile(&self, profile_dir: &Path) {
+        if let Err(e) = preseed_profile(profile_dir, &self.codename, &self.chromium) {
+            tracing::error!("Cannot preseed profile of {}: {}", self.codename, e);
+        }
+    }
+
+    /// Creates the profile of an isolated app and writes the settings kept
+    /// in it. `create` does this before writing the launcher.
+    pub fn prepare_profile(&self) -> Result<()> {
+        let isolated = self
+            .web_browser
+            .capabilities()
+            .isolated_profile
+            .resolve(self.isolate_profile);
+
+        if !isolated || !self.web_browser.is_installed() {
+            return Ok(());
+        }
+
+        let profile_dir = self.profile_dir();
+
+        tracing::info!("Creating profile directory in: {:?}", &profile_dir);
+        create_dir_all(&profile_dir)?;
+
+        // a shared profile keeps the settings of its browser, none of its
+        // apps may overwrite those of the others
+        if self.shared().is_some() {
+            return Ok(());
+        }
+
+        match self.web_browser._type.family() {
+            "firefox" => self.firefox_profile(&profile_dir)?,
+            "epiphany" => self.epiphany_profile(&profile_dir)?,
+            "chromium" => self.chromium_profile(&profile_dir),
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn exec_string(&self, url: &str) -> String {
+        if !self.web_browser.is_installed() {
+            return String::new();
+        }
+
+        // the template only leaves out what the capabilities make optional
+        let isolated = self
+            .web_browser
+            .capabilities()
+            .isolated_profile
+            .resolve(self.isolate_profile);
+
+        let profile = match isolated {
+            true => self.profile_dir().to_string_lossy().to_string(),
+            false => String::new(),
+        };
+
+        let private_flag = if self.is_incognito {
+            self.web_browser.private_flag.as_str()
+        } else {
+            ""
+        };
+
+        // apps sharing a profile open their windows in the instance already
+        // running it, which --no-remote would refuse
+        let no_remote = match self.shared() {
+            Some(_) => "",
+            None => "--no-remote",
+        };
+
+        let parameters = match self.web_browser._type.family() {
+            "chromium" => format!(
+                "{} {}",
+                chromium_flag(&self.extensions),
+                self.custom_parameters
+            ),
+            _ => self.custom_parameters.clone(),
+        };
+
+        // a detected executable is one path, whatever it holds
+        let exec = match Path::new(&self.exec).is_file() {
+            true => quote_exec_arg(&self.exec),
+            false => self.exec.clone(),
+        };
 
-        let mut file = File::create(&path)
-            .unwrap_or_else(|_| panic!("failed to create user.js in {:?}", path));
+        let command = expand_exec_template(
+            &self.web_browser.exec_template,
+            &[
+                ("exec", &exec),
+                ("url", url),
+                ("profile", &profile),
+                ("class", &self.wm_class()),
+                ("private_flag", private_flag),
+                ("no_remote", no_remote),
+                ("parameters", parameters.trim()),
+            ],
+        );
 
-        file.write_all(content).is_ok()
+        strip_unknown_flags(
+            &command,
+            self.web_browser._type.family(),
+            self.web_browser.version.as_ref(),
+        )
     }
 
-    fn create_user_chrome_css(&self, path: PathBuf, create_navbar: bool) -> bool {
-        let user_chrome_css =
-            include_bytes!("../data/runtime/firefox/profile/chrome/userChrome.css");
+    /// Options of this app that the installed browser is too old for.
+    pub fn outdated_options(&self) -> Vec<(WebAppOption, Version)> {
+        outdated_options(&self.web_browser, self.isolate_profile)
+    }
 
-        let mut file = File::create(&path)
-            .unwrap_or_else(|_| panic!("cant create userChrome.css in {:?}", path));
+    /// Directory holding the browser profile of this web app.
+    pub fn profile_dir(&self) -> PathBuf {
+        if let Some(profile) = self.shared() {
+            return profile.path;
+        }
 
-        if create_navbar {
-            file.write_all(b"").is_ok()
-        } else {
-            file.write_all(user_chrome_css).is_ok()
+        if let Some(location) = &self.profile_location {
+            return location.clone();
         }
+
+        self.web_browser.app_profile_dir(&self.codename)
     }
 
-    fn exec_firefox(&self) -> String {
-        let profile_path = self.web_browser.profile_path.join(&self.codename);
-        let user_js_path = profile_path.join("user.js");
-        let mut user_chrome_css = profile_path.join("chrome");
+    /// The shared profile this app uses. One that was deleted meanwhile is
+    /// replaced by a profile of the app's own.
+    pub fn shared(&self) -> Option<SharedProfile> {
+        let name = self.shared_profile.as_ref()?;
+        let profile = SharedProfile::find(name);
+
+        if profile.is_none() {
+            tracing::warn!("Profile {} of {} not found", name, self.codename);
+        }
 
-        tracing::info!("Creating profile directory in: {:?}", &profile_path);
-        create_dir_all(&profile_path)
-            .unwrap_or_else(|_| panic!("cant create profile dir in {:?}", &profile_path));
-        create_dir_all(&user_chrome_css)
-            .unwrap_or_else(|_| panic!("cant create chrome dir in {:?}", &user_chrome_css));
+        profile
+    }
 
-        user_chrome_css = user_chrome_css.join("userChrome.css");
+    /// Drops the settings written into the profile, which an app on a shared
+    /// profile cannot have. Returns whether there were any.
+    pub fn drop_profile_settings(&mut self) -> bool {
+        let had_settings = !self.prefs.is_empty()
+            || !self.extensions.is_empty()
+            || self.layout != Layout::default()
+            || self.tint != Tint::default()
+            || self.chromium != ChromiumSettings::default();
+
+        self.prefs.clear();
+        self.extensions.clear();
+        self.layout = Layout::default();
+        self.tint = Tint::default();
+        self.chromium = ChromiumSettings::default();
+
+        had_settings
+    }
 
-        self.create_firefox_userjs(user_js_path);
-        self.create_user_chrome_css(user_chrome_css, self.navbar);
+    /// Whether a Flatpak browser is kept away from the profile of this app
+    /// by its sandbox permissions.
+    pub fn profile_blocked(&self) -> bool {
+        self.isolate_profile
+            && Flatpak::system().profile_blocked(&self.web_browser, &self.profile_dir())
+    }
 
-        let profile_path = profile_path.to_str().unwrap();
+    /// Grants the Flatpak browser access to the profile with a per-user
+    /// override.
+    pub fn allow_profile_access(&self) -> Result<()> {
+        let id = flatpak_id(&self.web_browser)
+            .ok_or_else(|| anyhow!("{} is not a Flatpak.", self.web_browser.name))?;
 
-        let mut exec_string = format!(
-            "{} --class WebApp-{} --name WebApp-{} --profile {} --no-remote ",
-            self.exec, self.codename, self.codename, profile_path
-        );
+        Flatpak::system().allow(&id, &self.profile_dir())
+    }
 
-        if self.is_incognito {
-            exec_string.push_str("--private-window ");
+    /// Moves the profile into the data directory of the Flatpak browser,
+    /// which it can always reach, and rewrites the launcher to use it.
+    pub fn move_profile_to_sandbox(&mut self) -> Result<()> {
+        let id = flatpak_id(&self.web_browser)
+            .ok_or_else(|| anyhow!("{} is not a Flatpak.", self.web_browser.name))?;
+
+        let from = self.profile_dir();
+        let name = from
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid profile directory {:?}.", from))?;
+        let target = sandbox_profile_root(&id).join(name);
+
+        // a shared profile moves for all of its apps
+        if let Some(profile) = self.shared() {
+            profile.relocate(&target)?;
+            return self.create();
         }
 
-        if !self.custom_parameters.is_empty() {
-            exec_string.push_str(&format!("{} ", self.custom_parameters));
+        if from.exists() {
+            move_profile_dir(&from, &target)?;
         }
 
-        exec_string.push_str(&self.url);
+        self.profile_location = Some(target);
+        self.create()
+    }
 
-        exec_string
+    fn wm_class(&self) -> String {
+        match self.web_browser._type.family() {
+            "epiphany" => format!("{}{}", EPIPHANY_APP_PREFIX, self.codename),
+            _ => format!("WebApp-{}", self.codename),
+        }
     }
 
-    fn exec_chromium(&self) -> String {
-        let mut exec_string = format!(
-            "{} --app={} --class=WebApp-{} --name=WebApp-{} ",
-            self.exec, self.url, self.codename, self.codename
+    /// Launcher contents as `create` would write them, keeping unknown keys.
+    /// Leaves the profile alone, see `prepare_profile`.
+    pub fn to_desktop_entry(&self) -> DesktopEntry {
+        let mut desktop_entry = self.desktop_entry.clone();
+        let entry = desktop_entry.group_mut(DESKTOP_ENTRY_GROUP);
+
+        entry.set("Version", "1.0");
+        entry.set("Name", &self.name);
+
+        if self.description.is_empty() {
+            entry.set("Comment", "Web App");
+        } else {
+            entry.set("Comment", &self.description);
+        }
+
+        entry.set("Exec", &self.exec_string(&self.url));
+        entry.set_bool("Terminal", false);
+        entry.set("Type", "Application");
+        entry.set("Icon", &self.icon);
+        entry.set_list("Categories", &["GTK", &self.category]);
+        entry.set_list(
+            "MimeType",
+            &["text/html", "text/xml", "application/xhtml_xml"],
         );
+        entry.set("StartupWMClass", &self.wm_class());
+        entry.set_bool("StartupNotify", true);
+        entry.set_bool("X-MultipleArgs", false);
+        entry.set("X-WebApp-Browser", &self.web_browser.name);
+        entry.set("X-WebApp-URL", &self.url);
+        entry.set_bool("X-WebApp-Navbar", self.navbar);
+        entry.set_bool("X-WebApp-PrivateWindow", self.is_incognito);
+        entry.set_bool("X-WebApp-Isolated", self.isolate_profile);
+        entry.set("X-WebApp-CustomParameters", &self.custom_parameters);
+        entry.set("X-WebApp-Version", &LAUNCHER_VERSION.to_string());
+
+        if self.prefs.is_empty() {
+            entry.remove("X-WebApp-FirefoxPrefs");
+        } else {
+            let prefs: Vec<String> = self.prefs.iter().map(PrefOverride::to_string).collect();
+            entry.set_list("X-WebApp-FirefoxPrefs", &prefs);
+        }
 
-        if self.isolate_profile {
-            let profile_dir = self.web_browser.profile_path.join(&self.codename);
+        match self.layout {
+            Layout::Minimal => entry.remove("X-WebApp-Layout"),
+            layout => entry.set("X-WebApp-Layout", layout.as_str()),
+        }
 
-            tracing::info!("Creating profile directory in: {:?}", &profile_dir);
-            let _ = create_dir_all(&profile_dir);
-            let profile_path = profile_dir.to_str().unwrap();
-            exec_string.push_str(&format!("--user-data-dir={} ", profile_path));
+        match self.tint {
+            Tint::None => entry.remove("X-WebApp-Tint"),
+            tint => entry.set("X-WebApp-Tint", &tint.to_string()),
         }
 
-        if self.is_incognito {
-            if self.web_browser.name.starts_with("Microsoft Edge") {
-                exec_string.push_str("--inprivate ");
-            } else {
-                exec_string.push_str("--incognito ");
-            }
+        // only settings that differ from the defaults are written
+        let defaults = ChromiumSettings::default();
+
+        match self.chromium.zoom == defaults.zoom {
+            true => entry.remove("X-WebApp-Zoom"),
+            false => entry.set("X-WebApp-Zoom", &self.chromium.zoom.to_string()),
         }
 
-        if !self.custom_parameters.is_empty() {
-            exec_string.push_str(&format!("{} ", self.custom_parameters));
+        match self.chromium.dark_mode == defaults.dark_mode {
+            true => entry.remove("X-WebApp-DarkMode"),
+            false => entry.set_bool("X-WebApp-DarkMode", self.chromium.dark_mode),
         }
 
-        exec_string
-    }
+        match self.chromium.translate == defaults.translate {
+            true => entry.remove("X-WebApp-Translate"),
+            false => entry.set_bool("X-WebApp-Translate", self.chromium.translate),
+        }
+
+        match &self.chromium.download_dir {
+            Some(dir) => entry.set("X-WebApp-DownloadDir", &dir.to_string_lossy()),
+            None => entry.remove("X-WebApp-DownloadDir"),
+        }
 
-    fn exec_falkon(&self) -> String {
-        let mut exec_string = String::new();
+        if self.extensions.is_empty() {
+            entry.remove("X-WebApp-Extensions");
+        } else {
+            let extensions: Vec<String> = self
+                .extensions
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            entry.set_list("X-WebApp-Extensions", &extensions);
+        }
 
-        if self.isolate_profile {
-            let profile_dir = self.web_browser.profile_path.join(&self.codename);
-            tracing::info!("Creating profile directory in: {:?}", &profile_dir);
-            let _ = create_dir_all(&profile_dir);
+        match &self.shared_profile {
+            Some(name) => entry.set("X-WebApp-SharedProfile", name),
+            None => entry.remove("X-WebApp-SharedProfile"),
+        }
 
-            let profile_path = profile_dir.to_str().unwrap();
+        match &self.profile_location {
+            Some(location) => entry.set("X-WebApp-Profile", &location.to_string_lossy()),
+            None => entry.remove("X-WebApp-Profile"),
+        }
 
-            exec_string = format!(
-                "{} --portable --wmclass WebApp-{} --profile {} ",
-                self.exec, self.codename, profile_path
-            );
+        if self.keywords.is_empty() {
+            entry.remove("Keywords");
+        } else {
+            entry.set_list("Keywords", &self.keywords);
         }
 
-        if self.is_incognito {
-            exec_string.push_str("--private-browsing ");
+        entry.remove_localized("Name");
+        entry.remove_localized("Comment");
+
+        for translation in self.translations.iter().filter(|t| !t.locale.is_empty()) {
+            if !translation.name.is_empty() {
+                entry.set_localized("Name", &translation.locale, &translation.name);
+            }
+            if !translation.comment.is_empty() {
+                entry.set_localized("Comment", &translation.locale, &translation.comment);
+            }
         }
 
-        if !self.custom_parameters.is_empty() {
-            exec_string.push_str(&format!("{} ", self.custom_parameters));
+        // action groups we did not write ourselves are left alone
+        let mut action_ids: Vec<String> = entry
+            .get_list("Actions")
+            .into_iter()
+            .filter(|id| {
+                desktop_entry
+                    .group(&action_group_name(id))
+                    .is_some_and(|group| group.get("X-WebApp-URL").is_none())
+            })
+            .collect();
+
+        let owned: Vec<String> = desktop_entry
+            .groups()
+            .filter(|group| {
+                group.name.starts_with("Desktop Action ") && group.get("X-WebApp-URL").is_some()
+            })
+            .map(|group| group.name.clone())
+            .collect();
+
+        for name in owned {
+            desktop_entry.remove_group(&name);
         }
 
-        exec_string.push_str(&format!("--no-remote --current-tab {}", self.url));
+        for action in self
+            .actions
+            .iter()
+            .filter(|action| !action.name.is_empty() && url_valid(&action.url))
+        {
+            let id = unique_action_id(&action.name, &action_ids);
+            let group = desktop_entry.group_mut(&action_group_name(&id));
 
-        exec_string
-    }
+            group.set("Name", &action.name);
+            group.set("Exec", &self.exec_string(&action.url));
+            group.set("X-WebApp-URL", &action.url);
 
-    fn exec_string(&self) -> String {
-        match self.web_browser._type {
-            BrowserType::Firefox => self.exec_firefox(),
-            BrowserType::FirefoxFlatpak => self.exec_firefox(),
-            BrowserType::Chromium => self.exec_chromium(),
-            BrowserType::ChromiumFlatpak => self.exec_chromium(),
-            BrowserType::Falkon => self.exec_falkon(),
-            BrowserType::FalkonFlatpak => self.exec_falkon(),
-            BrowserType::NoBrowser => String::new(),
+            action_ids.push(id);
+        }
+
+        let entry = desktop_entry.group_mut(DESKTOP_ENTRY_GROUP);
+
+        if action_ids.is_empty() {
+            entry.remove("Actions");
+        } else {
+            entry.set_list("Actions", &action_ids);
         }
+
+        desktop_entry
     }
 
     pub fn create(&self) -> Result<()> {
-        let mut output = File::create(&self.path)?;
-
-        writeln!(output, "[Desktop Entry]")?;
-        writeln!(output, "Version=1.0")?;
-        writeln!(output, "Name={}", self.name)?;
-        writeln!(output, "Comment=Web App")?;
-        writeln!(output, "Exec={}", self.exec_string())?;
-        writeln!(output, "Terminal=false")?;
-        writeln!(output, "Type=Application")?;
-        writeln!(output, "Icon={}", self.icon)?;
-        writeln!(output, "Categories=GTK;{};", self.category)?;
-        writeln!(output, "MimeType=text/html;text/xml;application/xhtml_xml;")?;
-        writeln!(output, "StartupWMClass=WebApp-{}", self.codename)?;
-        writeln!(output, "StartupNotify=true")?;
-        writeln!(output, "X-MultipleArgs=false")?;
-        writeln!(output, "X-WebApp-Browser={}", self.web_browser.name)?;
-        writeln!(output, "X-WebApp-URL={}", self.url)?;
-        writeln!(output, "X-WebApp-Navbar={}", self.navbar)?;
-        writeln!(output, "X-WebApp-PrivateWindow={}", self.is_incognito)?;
-        writeln!(output, "X-WebApp-Isolated={}", self.isolate_profile)?;
-        writeln!(
-            output,
-            "X-WebApp-CustomParameters={}",
-            self.custom_parameters
-        )?;
+        self.prepare_profile()?;
+        backup_launcher(&self.codename, &self.path)?;
+        self.to_desktop_entry().write(&self.path)
+    }
+
+    /// Writes this launcher in place of `previous`, the same app before an
+    /// edit. When the profile directory changes with the browser, the old
+    /// profile moves along if the engine stays the same and is removed
+    /// otherwise, since the new browser could not read it.
+    pub fn replace(&self, previous: &WebAppLauncher) -> Result<()> {
+        let from = previous.profile_dir();
+        let to = self.profile_dir();
+
+        // a shared profile stays with the apps still using it
+        if previous.shared().is_some() || from == to || !from.exists() {
+            return self.create();
+        }
+
+        let keep = self.isolate_profile
+            && self.shared().is_none()
+            && self.web_browser._type.family() == previous.web_browser._type.family();
+
+        if keep && !to.exists() {
+            move_profile_dir(&from, &to)?;
+
+            if let Err(e) = self.create() {
+                move_profile_dir(&to, &from)?;
+                return Err(e);
+            }
+
+            return Ok(());
+        }
+
+        self.create()?;
+
+        if keep {
+            tracing::warn!(
+                "Not moving profile {}, {} already exists",
+                from.display(),
+                to.display()
+            );
+        } else {
+            remove_dir_all(&from)?;
+            tracing::info!("Removed profile directory, from: {}", from.display());
+        }
 
         Ok(())
     }
 
+    /// Puts back the most recent backup of a launcher, stepping one version back.
+    /// The current launcher is backed up first, so restoring again undoes it.
+    pub fn restore(codename: &str) -> Result<PathBuf> {
+        let backup = launcher_backups(codename)
+            .pop()
+            .ok_or_else(|| anyhow!("No backup found for {}.", codename))?;
+        let path = desktop_filepath(&format!("webapp-{}.desktop", codename));
+
+        backup_launcher(codename, &path)?;
+        write_atomic(&path, &fs::read(&backup)?)?;
+        remove_file(&backup)?;
+
+        Ok(path)
+    }
+
     pub fn delete(&self) -> Result<()> {
         let exist = self.path.as_path().exists();
 
         match exist {
             true => {
+                backup_launcher(&self.codename, &self.path)?;
                 remove_file(&self.path)?;
             }
             false => {
@@ -453,7 +1457,16 @@ impl WebAppLauncher {
             }
         }
 
-        let profile_path = self.web_browser.profile_path.join(&self.codename);
+        // a shared profile goes with the last app using it
+        if let Some(profile) = self.shared() {
+            if profile.users(Some(&self.path)).is_empty() {
+                profile.remove()?;
+            }
+
+            return Ok(());
+        }
+
+        let profile_path = self.profile_dir();
 
         if remove_dir_all(&profile_path).is_ok() {
             tracing::info!(
@@ -466,6 +1479,95 @@ impl WebAppLauncher {
     }
 }
 
+/// Fills `{name}` placeholders of a command line template. Words in square
+/// brackets form a group, and a word or group is left out when one of its
+/// placeholders is empty or unknown, so `[--profile {profile}]` disappears
+/// for web apps without their own profile. Words taking a value are quoted
+/// for the Exec key, `{exec}` is a command line whose words are quoted one
+/// by one, and `{private_flag}` and `{parameters}` are written as they are.
+pub fn expand_exec_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut groups: Vec<Vec<&str>> = Vec::new();
+    let mut in_group = false;
+
+    for word in template.split_whitespace() {
+        let (opens, word) = match word.strip_prefix('[') {
+            Some(word) => (true, word),
+            None => (false, word),
+        };
+        let (closes, word) = match word.strip_suffix(']') {
+            Some(word) => (true, word),
+            None => (false, word),
+        };
+
+        match groups.last_mut() {
+            Some(group) if in_group && !opens => group.push(word),
+            _ => groups.push(vec![word]),
+        }
+
+        in_group = (in_group || opens) && !closes;
+    }
+
+    let expand = |word: &str| -> Option<String> {
+        let mut expanded = word.to_string();
+        let mut quote = false;
+
+        if let Some(name) =
+            placeholders(word).find(|name| values.iter().all(|(known, _)| known != name))
+        {
+            tracing::warn!("Leaving out {} with unknown placeholder {{{}}}", word, name);
+            return None;
+        }
+
+        for (name, value) in values {
+            let placeholder = format!("{{{}}}", name);
+
+            if expanded.contains(&placeholder) {
+                if value.is_empty() {
+                    return None;
+                }
+
+                // browsers.toml may run a browser through another command,
+                // e.g. `flatpak run org.example.Browser`
+                let value = match *name {
+                    "exec" => split_exec(value)
+                        .iter()
+                        .map(|word| quote_exec_arg(word))
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                    _ => value.to_string(),
+                };
+
+                expanded = expanded.replace(&placeholder, &value);
+                quote |= !matches!(*name, "exec" | "private_flag" | "parameters");
+            }
+        }
+
+        match quote {
+            true => Some(quote_exec_arg(&expanded)),
+            false => Some(expanded),
+        }
+    };
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            group
+                .into_iter()
+                .map(expand)
+                .collect::<Option<Vec<String>>>()
+        })
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// names of the `{name}` placeholders in a template word
+fn placeholders(word: &str) -> impl Iterator<Item = &str> {
+    word.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+}
+
 pub fn get_webapps() -> Vec<Result<WebAppLauncher>> {
     let mut webapps = Vec::new();
 
@@ -496,15 +1598,158 @@ pub fn get_webapps() -> Vec<Result<WebAppLauncher>> {
     webapps
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// How an engine handles one of the web app options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    Unsupported,
+    Optional,
+    Always,
+}
+
+impl Support {
+    /// The value an option ends up with, whatever was asked for.
+    pub fn resolve(self, requested: bool) -> bool {
+        match self {
+            Support::Unsupported => false,
+            Support::Optional => requested,
+            Support::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Toolbar shown through `userChrome.css`.
+    pub navbar: Support,
+    pub isolated_profile: Support,
+    pub private_window: Support,
+}
+
+impl Capabilities {
+    /// Rejects options that were asked for explicitly but cannot work.
+    pub fn check(
+        &self,
+        browser: &str,
+        navbar: Option<bool>,
+        isolated_profile: Option<bool>,
+        private_window: Option<bool>,
+    ) -> Result<()> {
+        let options = [
+            ("the navigation bar", self.navbar, navbar),
+            (
+                "an isolated profile",
+                self.isolated_profile,
+                isolated_profile,
+            ),
+            ("private windows", self.private_window, private_window),
+        ];
+
+        for (option, support, requested) in options {
+            match (support, requested) {
+                (Support::Unsupported, Some(true)) => {
+                    return Err(anyhow!("{} does not support {}.", browser, option));
+                }
+                (Support::Always, Some(false)) => {
+                    return Err(anyhow!("{} always uses {}.", browser, option));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub enum BrowserType {
     NoBrowser,
     Firefox,
     FirefoxFlatpak,
+    FirefoxSnap,
     Chromium,
     ChromiumFlatpak,
+    ChromiumSnap,
     Falkon,
     FalkonFlatpak,
+    Epiphany,
+    EpiphanyFlatpak,
+}
+
+impl BrowserType {
+    pub fn capabilities(&self) -> Capabilities {
+        let (navbar, isolated_profile, private_window) = match self.family() {
+            // Firefox has no app mode, a profile of its own is what hides
+            // the toolbar and keeps the window apart
+            "firefox" => (Support::Optional, Support::Always, Support::Optional),
+            "chromium" => (Support::Unsupported, Support::Optional, Support::Optional),
+            // --no-remote against the default profile fails while the
+            // browser is already open
+            "falkon" => (Support::Unsupported, Support::Always, Support::Optional),
+            "epiphany" => (Support::Unsupported, Support::Always, Support::Unsupported),
+            _ => (
+                Support::Unsupported,
+                Support::Unsupported,
+                Support::Unsupported,
+            ),
+        };
+
+        Capabilities {
+            navbar,
+            isolated_profile,
+            private_window,
+        }
+    }
+
+    /// Default command line of the engine, see `expand_exec_template`. Only
+    /// options the capabilities make optional are left out when unused.
+    pub fn exec_template(&self) -> &'static str {
+        match self.family() {
+            "firefox" => {
+                "{exec} --class {class} --name {class} --profile {profile} {no_remote} {private_flag} {parameters} {url}"
+            }
+            "chromium" => {
+                "{exec} --app={url} --class={class} --name={class} --user-data-dir={profile} {private_flag} {parameters}"
+            }
+            "falkon" => {
+                "{exec} --wmclass {class} --portable --profile {profile} {private_flag} {parameters} {no_remote} --current-tab {url}"
+            }
+            "epiphany" => "{exec} --application-mode --profile={profile} {parameters} {url}",
+            _ => "",
+        }
+    }
+
+    pub fn private_flag(&self) -> &'static str {
+        match self.family() {
+            "firefox" => "--private-window",
+            "chromium" => "--incognito",
+            "falkon" => "--private-browsing",
+            _ => "",
+        }
+    }
+
+    pub fn is_flatpak(&self) -> bool {
+        matches!(
+            self,
+            BrowserType::FirefoxFlatpak
+                | BrowserType::ChromiumFlatpak
+                | BrowserType::FalkonFlatpak
+                | BrowserType::EpiphanyFlatpak
+        )
+    }
+
+    pub fn family(&self) -> &'static str {
+        match self {
+            BrowserType::Firefox | BrowserType::FirefoxFlatpak | BrowserType::FirefoxSnap => {
+                "firefox"
+            }
+            BrowserType::Chromium | BrowserType::ChromiumFlatpak | BrowserType::ChromiumSnap => {
+                "chromium"
+            }
+            BrowserType::Falkon | BrowserType::FalkonFlatpak => "falkon",
+            BrowserType::Epiphany | BrowserType::EpiphanyFlatpak => "epiphany",
+            BrowserType::NoBrowser => "",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -514,11 +1759,16 @@ pub struct Browser {
     pub exec: String,
     pub test: PathBuf,
     pub profile_path: PathBuf,
+    pub exec_template: String,
+    pub private_flag: String,
+    pub version: Option<Version>,
+    /// Name shown in the browser dropdowns, with the version when known.
+    pub label: String,
 }
 
 impl AsRef<str> for Browser {
     fn as_ref(&self) -> &str {
-        &self.name
+        &self.label
     }
 }
 
@@ -531,32 +1781,24 @@ impl Browser {
         profile_path: &str,
     ) -> Self {
         let name = name.to_string();
+        let label = name.clone();
 
-        let mut test = PathBuf::new();
-        let mut exe_path = PathBuf::new();
-
-        let base = home_dir();
-        let data_home = base.join(".local/share");
-
-        if exec.starts_with(".local/share/") {
-            let flatpak_path: Vec<&str> = exec.split(".local/share/").collect();
-            let path = data_home.join(flatpak_path[1]);
-            exe_path.push(path);
+        // bare command names are looked up in PATH, anything else is a location
+        let exec = if exec.contains('/') {
+            paths::resolve(exec).to_string_lossy().to_string()
         } else {
-            exe_path.push(exec)
-        }
+            exec.to_string()
+        };
 
-        if test_path.starts_with(".local/share") {
-            let flatpak_path: Vec<&str> = test_path.split(".local/share/").collect();
-            let path = data_home.join(flatpak_path[1]);
-            test.push(path);
+        let test = if test_path.is_empty() {
+            PathBuf::new()
         } else {
-            test.push(test_path)
-        }
-
-        let exec = exe_path.to_str().unwrap().to_string();
+            paths::resolve(test_path)
+        };
 
-        let profile_path = base.join(profile_path);
+        let profile_path = paths::resolve(profile_path);
+        let exec_template = _type.exec_template().to_string();
+        let private_flag = _type.private_flag().to_string();
 
         Self {
             _type,
@@ -564,7 +1806,35 @@ impl Browser {
             exec,
             test,
             profile_path,
+            exec_template,
+            private_flag,
+            version: None,
+            label,
+        }
+    }
+
+    /// Installed version as far as it was probed, see `version.rs`.
+    pub fn with_version(mut self) -> Self {
+        self.version = cached_version(&self);
+
+        if let Some(version) = &self.version {
+            self.label = format!("{} {}", self.name, version);
+        }
+
+        self
+    }
+
+    /// Replaces the engine defaults for browsers whose flags differ.
+    pub fn with_flags(mut self, exec_template: Option<&str>, private_flag: Option<&str>) -> Self {
+        if let Some(exec_template) = exec_template {
+            self.exec_template = exec_template.to_string();
+        }
+
+        if let Some(private_flag) = private_flag {
+            self.private_flag = private_flag.to_string();
         }
+
+        self
     }
 
     pub fn web_browser(name: String) -> Option<Browser> {
@@ -575,33 +1845,103 @@ impl Browser {
     pub fn is_installed(&self) -> bool {
         !matches!(self._type, BrowserType::NoBrowser)
     }
-}
 
-pub fn get_supported_browsers() -> Vec<Browser> {
-    let mut test_browsers: Vec<Browser> = Vec::new();
+    pub fn capabilities(&self) -> Capabilities {
+        self._type.capabilities()
+    }
+
+    /// Directory holding the profiles of web apps made for this browser.
+    pub fn profile_root(&self) -> PathBuf {
+        // a confined snap can only write below ~/snap/<name>, so a profile
+        // root from elsewhere, e.g. browsers.toml, would break the launcher
+        if let Some(snap) = self.snap_name() {
+            let snap_dir = paths::home_dir().join("snap").join(&snap);
+
+            if !self.profile_path.starts_with(&snap_dir) {
+                return snap_dir.join("common/quick-webapps");
+            }
+        }
+
+        self.profile_path.clone()
+    }
 
-    let native_browsers = native_browsers();
-    let flatpak_browsers = flatpak_browsers();
-    let nix_browsers: Vec<Browser> = nix_browsers();
+    /// Default profile directory of the web app with this codename.
+    pub fn app_profile_dir(&self, codename: &str) -> PathBuf {
+        let root = self.profile_root();
 
-    test_browsers.extend(native_browsers);
-    test_browsers.extend(flatpak_browsers);
-    test_browsers.extend(nix_browsers);
+        match self._type.family() {
+            "epiphany" => root.join(format!("{}{}", EPIPHANY_APP_PREFIX, codename)),
+            _ => root.join(codename),
+        }
+    }
 
-    let mut browsers = Vec::new();
+    /// Name of the snap for browsers running under snap confinement.
+    pub fn snap_name(&self) -> Option<String> {
+        if !matches!(
+            self._type,
+            BrowserType::FirefoxSnap | BrowserType::ChromiumSnap
+        ) {
+            return None;
+        }
 
-    for browser in test_browsers {
-        let exists = browser.test.as_path().try_exists();
+        // commands other than the main one are exposed as <snap>.<app>
+        let command = Path::new(&self.exec).file_name()?.to_str()?;
 
-        match exists {
-            Ok(found) => match found {
-                true => browsers.push(browser),
-                false => continue,
-            },
-            Err(_) => continue,
+        command.split('.').next().map(str::to_string)
+    }
+}
+
+fn discover_browsers() -> Vec<Browser> {
+    // browsers from the config file come first and replace detected ones
+    // of the same name
+    let mut browsers = config_browsers();
+    let configured: HashSet<String> = browsers.iter().map(|b| b.name.clone()).collect();
+    let mut seen = HashSet::new();
+
+    for browser in path_browsers()
+        .into_iter()
+        .chain(desktop_file_browsers())
+        .chain(flatpak_browsers())
+        .chain(snap_browsers())
+        .filter(|browser| !configured.contains(&browser.name))
+    {
+        // the same binary is often reachable through several symlinks; names
+        // are kept apart since launchers refer to browsers by name
+        let target = fs::canonicalize(&browser.test).unwrap_or(browser.test.clone());
+
+        if seen.insert((browser.name.clone(), target)) {
+            browsers.push(browser);
         }
     }
 
+    browsers.into_iter().map(Browser::with_version).collect()
+}
+
+/// Probes the versions of the installed browsers and forgets the browsers
+/// found so far when that taught us something, so they are looked up again
+/// with their versions. Blocking, see `probe_versions`.
+pub fn probe_browser_versions() -> bool {
+    let learned = probe_versions(&get_supported_browsers());
+
+    if learned {
+        *BROWSERS.lock().unwrap() = None;
+    }
+
+    learned
+}
+
+pub fn get_supported_browsers() -> Vec<Browser> {
+    let mut cache = BROWSERS.lock().unwrap();
+
+    let mut browsers = match cache.as_ref() {
+        Some((found_at, browsers)) if found_at.elapsed() < BROWSERS_TTL => browsers.clone(),
+        _ => {
+            let browsers = discover_browsers();
+            *cache = Some((Instant::now(), browsers.clone()));
+            browsers
+        }
+    };
+
     if browsers.is_empty() {
         browsers.push(Browser::new(
             BrowserType::NoBrowser,
@@ -674,7 +2014,10 @@ pub async fn find_icons(icon_name: String, url: String) -> Vec<String> {
     let mut result: Vec<String> = Vec::new();
 
     result.extend(find_icon(icons_location(), icon_name.clone()).await);
-    result.extend(find_icon(system_fonts(), icon_name).await);
+
+    for location in system_icons_locations() {
+        result.extend(find_icon(location, icon_name.clone()).await);
+    }
 
     if url_valid(&url) {
         if let Ok(data) = download_favicon(&url).await {
@@ -740,7 +2083,7 @@ pub fn convert_raster_to_svg_format(img_slice: Bytes, save_path: &str) -> Result
             .add(image_element);
 
         // Save the SVG document
-        svg::save(save_path, &document)?;
+        write_atomic(Path::new(save_path), document.to_string().as_bytes())?;
     }
 
     Ok(())
@@ -777,8 +2120,8 @@ pub fn move_icon(path: String, output_name: String) -> String {
 
                 let _ = convert_raster_to_svg_format(content, &save_path);
             }
-        } else {
-            copy(&path, &save_path).unwrap();
+        } else if let Ok(content) = fs::read(&path) {
+            write_atomic(Path::new(&save_path), &content).unwrap();
         }
     }
 
@@ -835,3 +2178,376 @@ pub async fn image_handle(path: String) -> Option<pages::iconpicker::Icon> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // trimmed from a launcher made by webapp-manager and edited by hand
+    const LAUNCHER: &str = r#"#!/usr/bin/env xdg-open
+# made by hand
+[Desktop Entry]
+Version=1.0
+Name=Mail
+Name[pl]=Poczta
+Comment=Web App
+Exec=firefox --class WebApp-mail --profile "/home/me/My Profiles/mail" "https://mail.example.com/?q=100%%"
+Keywords=mail;web\;app;
+X-Custom-Key=kept
+# a comment between keys
+Icon=mail
+
+[Desktop Action Compose]
+Name=Compose
+Exec=firefox https://mail.example.com/compose
+"#;
+
+    #[test]
+    fn writes_entries_back_unchanged() {
+        let entry = DesktopEntry::parse(LAUNCHER);
+
+        assert_eq!(entry.to_string(), LAUNCHER);
+        assert_eq!(
+            entry.groups().map(|g| g.name.as_str()).collect::<Vec<_>>(),
+            ["Desktop Entry", "Desktop Action Compose"]
+        );
+    }
+
+    #[test]
+    fn reads_and_sets_keys() {
+        let mut entry = DesktopEntry::parse(LAUNCHER);
+        let group = entry.group_mut(DESKTOP_ENTRY_GROUP);
+
+        assert_eq!(
+            group.get_localized("Name", "pl_PL.UTF-8").unwrap(),
+            "Poczta"
+        );
+        assert_eq!(group.get_localized("Name", "de").unwrap(), "Mail");
+        assert_eq!(group.get_list("Keywords"), ["mail", "web;app"]);
+
+        group.set("Name", "Post");
+        group.set("Comment", " leading space");
+        group.set("X-New-Key", "added");
+
+        let written = entry.to_string();
+        let group = DesktopEntry::parse(&written);
+        let group = group.group(DESKTOP_ENTRY_GROUP).unwrap();
+
+        assert!(written.contains("Name=Post\nName[pl]=Poczta\n"));
+        assert!(written.contains("X-Custom-Key=kept\n# a comment between keys\n"));
+        assert!(written.contains("X-New-Key=added\n\n[Desktop Action Compose]"));
+        assert_eq!(group.get("Comment").unwrap(), " leading space");
+    }
+
+    // built by hand, `WebAppLauncher::new` looks through the installed apps
+    fn mail_launcher(browser: Browser) -> WebAppLauncher {
+        WebAppLauncher {
+            path: PathBuf::from("webapp-mail.desktop"),
+            codename: String::from("mail"),
+            exec: browser.exec.clone(),
+            web_browser: browser,
+            name: String::from("Mail"),
+            icon: String::from("mail"),
+            is_valid: true,
+            args: Vec::new(),
+            category: String::from("Office"),
+            url: String::from("https://mail.example.com"),
+            custom_parameters: String::new(),
+            isolate_profile: false,
+            navbar: false,
+            is_incognito: false,
+            description: String::new(),
+            keywords: Vec::new(),
+            translations: Vec::new(),
+            actions: Vec::new(),
+            prefs: Vec::new(),
+            extensions: Vec::new(),
+            layout: Layout::default(),
+            tint: Tint::default(),
+            chromium: ChromiumSettings::default(),
+            shared_profile: None,
+            profile_location: None,
+            desktop_entry: DesktopEntry::default(),
+        }
+    }
+
+    #[test]
+    fn edits_carry_the_profile_to_the_new_browser() {
+        let dir = std::env::temp_dir().join(format!("quick-webapps-edit-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let browser = |_type, name: &str| {
+            let profiles = dir.join(name);
+            Browser::new(_type, name, name, "", &profiles.to_string_lossy())
+        };
+
+        paths::with_data_home(&dir.join("data"), || {
+            let mut firefox = mail_launcher(browser(BrowserType::Firefox, "firefox"));
+            firefox.path = desktop_filepath("webapp-mail.desktop");
+            create_dir_all(desktop_filepath("")).unwrap();
+            firefox.isolate_profile = true;
+            firefox.create().unwrap();
+            fs::write(firefox.profile_dir().join("cookies.sqlite"), b"logins").unwrap();
+
+            // same engine, the profile moves along
+            let mut librewolf = firefox.clone();
+            librewolf.web_browser = browser(BrowserType::Firefox, "librewolf");
+            librewolf.replace(&firefox).unwrap();
+
+            assert!(!firefox.profile_dir().exists());
+            assert_eq!(
+                fs::read(librewolf.profile_dir().join("cookies.sqlite")).unwrap(),
+                b"logins"
+            );
+
+            // another engine could not read it
+            let mut chromium = librewolf.clone();
+            chromium.web_browser = browser(BrowserType::Chromium, "chromium");
+            chromium.replace(&librewolf).unwrap();
+
+            assert!(!librewolf.profile_dir().exists());
+            assert!(chromium.profile_dir().exists());
+        });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn shared_profiles_stay_while_orphans_use_them() {
+        let dir = std::env::temp_dir().join(format!("quick-webapps-shared-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let firefox = Browser::new(
+            BrowserType::Firefox,
+            "Firefox",
+            "firefox",
+            "",
+            &dir.join("firefox").to_string_lossy(),
+        );
+
+        paths::with_data_home(&dir.join("data"), || {
+            create_dir_all(desktop_filepath("")).unwrap();
+            let profile = SharedProfile::create("Work", &firefox).unwrap();
+
+            let mut mail = mail_launcher(firefox.clone());
+            mail.path = desktop_filepath("webapp-mail.desktop");
+            mail.isolate_profile = true;
+            mail.shared_profile = Some(profile.name.clone());
+            mail.create().unwrap();
+
+            // its browser is gone, so it cannot be read as a launcher
+            let orphan = desktop_filepath("webapp-chat.desktop");
+            fs::write(
+                &orphan,
+                "[Desktop Entry]\nName=Chat\nExec=gone --profile x\n\
+                 X-WebApp-Browser=Gone\nX-WebApp-SharedProfile=Work\n",
+            )
+            .unwrap();
+
+            mail.delete().unwrap();
+
+            assert!(profile.path.is_dir());
+            let users = profile.users(None);
+            assert_eq!(users.len(), 1);
+            assert_eq!(users[0].codename, "chat");
+            assert!(profile.delete().is_err());
+
+            fs::remove_file(&orphan).unwrap();
+            profile.delete().unwrap();
+
+            assert!(!profile.path.exists());
+        });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn apps_leave_the_settings_of_shared_profiles_alone() {
+        let dir = std::env::temp_dir().join(format!(
+            "quick-webapps-shared-settings-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        let firefox = Browser::new(
+            BrowserType::Firefox,
+            "Firefox",
+            "firefox",
+            "",
+            &dir.join("firefox").to_string_lossy(),
+        );
+
+        paths::with_data_home(&dir.join("data"), || {
+            create_dir_all(desktop_filepath("")).unwrap();
+            let profile = SharedProfile::create("Work", &firefox).unwrap();
+            fs::write(profile.path.join("user.js"), "// the browser's own\n").unwrap();
+
+            let mut mail = mail_launcher(firefox.clone());
+            mail.path = desktop_filepath("webapp-mail.desktop");
+            mail.isolate_profile = true;
+            mail.shared_profile = Some(profile.name.clone());
+            mail.prefs = vec![PrefOverride::parse("media.autoplay.default=5").unwrap()];
+            mail.layout = Layout::Tabs;
+            mail.create().unwrap();
+
+            assert_eq!(
+                fs::read_to_string(profile.path.join("user.js")).unwrap(),
+                "// the browser's own\n"
+            );
+            assert!(!profile.path.join("chrome").exists());
+
+            assert!(mail.drop_profile_settings());
+            assert!(mail.prefs.is_empty());
+            assert_eq!(mail.layout, Layout::default());
+            assert!(!mail.drop_profile_settings());
+        });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn writes_description_keywords_and_translations() {
+        let browser = Browser::new(BrowserType::Firefox, "Firefox", "firefox", "", "profiles");
+        let mut launcher = mail_launcher(browser);
+        let profile =
+            std::env::temp_dir().join(format!("quick-webapps-mail-{}", std::process::id()));
+        launcher.isolate_profile = true;
+        launcher.profile_location = Some(profile.clone());
+        launcher.description = String::from("Read and send mail");
+        launcher.keywords = parse_keywords("email, inbox");
+        launcher.translations = vec![WebAppTranslation {
+            locale: String::from("pl"),
+            name: String::from("Poczta"),
+            comment: String::from("Czytaj pocztę"),
+        }];
+
+        let written = launcher.to_desktop_entry().to_string();
+
+        assert!(written.contains("Comment=Read and send mail\n"));
+        assert!(written.contains("Keywords=email;inbox;\n"));
+        assert!(written.contains("Name[pl]=Poczta\n"));
+        assert!(written.contains("Comment[pl]=Czytaj pocztę\n"));
+        // writing the entry leaves the profile to `create`
+        assert!(!profile.exists());
+    }
+
+    #[test]
+    fn quotes_exec_arguments() {
+        let exec = expand_exec_template(
+            "{exec} [--profile {profile}] {private_flag} {parameters} {url}",
+            &[
+                ("exec", "/usr/bin/firefox"),
+                ("profile", "/home/me/My Profiles/mail"),
+                ("private_flag", ""),
+                ("parameters", "--new-window"),
+                ("url", "https://example.com/?a=1&b=50%"),
+            ],
+        );
+
+        assert_eq!(
+            exec,
+            "/usr/bin/firefox --profile \"/home/me/My Profiles/mail\" --new-window \"https://example.com/?a=1&b=50%%\""
+        );
+        assert_eq!(
+            split_exec(&exec),
+            [
+                "/usr/bin/firefox",
+                "--profile",
+                "/home/me/My Profiles/mail",
+                "--new-window",
+                "https://example.com/?a=1&b=50%",
+            ]
+        );
+        assert_eq!(quote_exec_arg("say \"hi\" $HOME"), r#""say \"hi\" \$HOME""#);
+    }
+
+    #[test]
+    fn splits_exec_commands() {
+        let expand = |exec: &str| {
+            expand_exec_template(
+                "{exec} --app={url} {unknown} [--profile {profile}]",
+                &[
+                    ("exec", exec),
+                    ("url", "https://example.com"),
+                    ("profile", ""),
+                ],
+            )
+        };
+
+        assert_eq!(
+            expand("flatpak run org.example.Browser"),
+            "flatpak run org.example.Browser --app=https://example.com"
+        );
+        assert_eq!(
+            split_exec(&expand(&quote_exec_arg("/opt/My Browser/browser"))),
+            ["/opt/My Browser/browser", "--app=https://example.com"]
+        );
+    }
+
+    #[test]
+    fn templates_follow_capabilities() {
+        let families = [
+            BrowserType::Firefox,
+            BrowserType::Chromium,
+            BrowserType::Falkon,
+            BrowserType::Epiphany,
+        ];
+
+        for (_type, requested) in families
+            .iter()
+            .flat_map(|_type| [(_type, true), (_type, false)])
+        {
+            let isolated = _type.capabilities().isolated_profile.resolve(requested);
+            let profile = if isolated { "/profiles/mail" } else { "" };
+
+            let exec = expand_exec_template(
+                _type.exec_template(),
+                &[
+                    ("exec", "browser"),
+                    ("url", "https://example.com"),
+                    ("profile", profile),
+                    ("class", "WebApp-mail"),
+                    ("private_flag", ""),
+                    ("no_remote", "--no-remote"),
+                    ("parameters", ""),
+                ],
+            );
+            let args = split_exec(&exec);
+
+            assert!(!exec.contains('{'), "{}", exec);
+            assert_eq!(exec.contains("/profiles/mail"), isolated, "{}", exec);
+            // a flag never loses the value it takes
+            assert!(
+                args.windows(2)
+                    .all(|pair| pair[0] != "--profile" || pair[1] == "/profiles/mail"),
+                "{}",
+                exec
+            );
+            assert!(!args.iter().any(|arg| arg.ends_with('=')), "{}", exec);
+        }
+    }
+
+    #[test]
+    fn shared_profiles_leave_out_no_remote() {
+        let expand = |no_remote: &str| {
+            expand_exec_template(
+                BrowserType::Firefox.exec_template(),
+                &[
+                    ("exec", "firefox"),
+                    ("url", "https://mail.example.com"),
+                    ("profile", "/profiles/work"),
+                    ("class", "WebApp-mail"),
+                    ("private_flag", ""),
+                    ("no_remote", no_remote),
+                    ("parameters", ""),
+                ],
+            )
+        };
+
+        assert_eq!(
+            expand("--no-remote"),
+            "firefox --class WebApp-mail --name WebApp-mail --profile /profiles/work --no-remote https://mail.example.com"
+        );
+        assert_eq!(
+            expand(""),
+            "firefox --class WebApp-mail --name WebApp-mail --profile /profiles/work https://mail.example.com"
+        );
+    }
+}
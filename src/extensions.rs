@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::common::{quote_exec_arg, write_atomic};
+
+// ids of the extensions we put into a profile, so removing one from a web
+// app does not touch extensions installed from the browser itself
+const INSTALLED_FILE: &str = "quick-webapps-extensions";
+
+/// Firefox pref keeping extensions it did not install itself disabled.
+pub const AUTO_DISABLE_SCOPES: &str = "extensions.autoDisableScopes";
+
+/// Reads one file out of an extension package, which is a zip archive.
+fn zip_entry(path: &Path, name: &str) -> Result<Vec<u8>> {
+    let not_a_package = || anyhow!("{} is not an extension package.", path.display());
+
+    let mut archive = ZipArchive::new(File::open(path)?).map_err(|_| not_a_package())?;
+    let mut entry = archive.by_name(name).map_err(|_| not_a_package())?;
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content)?;
+
+    Ok(content)
+}
+
+fn gecko_id(manifest: &Value) -> Option<String> {
+    ["browser_specific_settings", "applications"]
+        .iter()
+        .find_map(|key| manifest.get(key)?.get("gecko")?.get("id")?.as_str())
+        .map(str::to_string)
+}
+
+/// Add-on id of a Firefox extension package, which Firefox expects as the
+/// file name in the profile.
+pub fn xpi_id(path: &Path) -> Result<String> {
+    let manifest = zip_entry(path, "manifest.json")?;
+    let manifest: Value = serde_json::from_slice(&manifest)?;
+
+    gecko_id(&manifest).ok_or_else(|| {
+        anyhow!(
+            "{} does not name its add-on id, Firefox cannot install it from a profile.",
+            path.display()
+        )
+    })
+}
+
+fn is_xpi(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "xpi")
+}
+
+fn is_unpacked(path: &Path) -> bool {
+    path.join("manifest.json").is_file()
+}
+
+/// Whether browsers of `family` can load the extension at `path`. Firefox
+/// takes `.xpi` packages, Chromium unpacked extension directories.
+pub fn check(family: &str, path: &Path) -> Result<()> {
+    match family {
+        "firefox" if is_xpi(path) => xpi_id(path).map(|_| ()),
+        "firefox" => Err(anyhow!(
+            "{} is not an .xpi package, Firefox web apps only take those.",
+            path.display()
+        )),
+        "chromium" if path.to_string_lossy().contains(',') => Err(anyhow!(
+            "{} contains a comma, Chromium cannot load extensions from such paths.",
+            path.display()
+        )),
+        "chromium" if is_unpacked(path) => Ok(()),
+        "chromium" => Err(anyhow!(
+            "{} is not an unpacked extension, Chromium web apps need a directory with a manifest.json.",
+            path.display()
+        )),
+        _ => Err(anyhow!("Extensions only work with Firefox and Chromium browsers.")),
+    }
+}
+
+/// Copies the packages into the `extensions` directory of a Firefox profile
+/// and removes the ones we installed before that are no longer wanted.
+pub fn install_firefox(profile: &Path, extensions: &[PathBuf]) -> Result<()> {
+    let dir = profile.join("extensions");
+    let installed_file = profile.join(INSTALLED_FILE);
+    let previous = fs::read_to_string(&installed_file).unwrap_or_default();
+
+    let mut installed = HashSet::new();
+
+    for path in extensions {
+        let id = match xpi_id(path) {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::warn!("Skipping extension: {}", e);
+                continue;
+            }
+        };
+        let target = dir.join(format!("{}.xpi", id));
+
+        if fs::read(&target).ok() != fs::read(path).ok() {
+            fs::create_dir_all(&dir)?;
+            fs::copy(path, &target)?;
+            tracing::info!("Installed {} into {}", id, profile.display());
+        }
+
+        installed.insert(id);
+    }
+
+    for id in previous.lines().filter(|id| !installed.contains(*id)) {
+        if fs::remove_file(dir.join(format!("{}.xpi", id))).is_ok() {
+            tracing::info!("Removed {} from {}", id, profile.display());
+        }
+    }
+
+    let mut ids: Vec<&String> = installed.iter().collect();
+    ids.sort();
+
+    match ids.is_empty() {
+        true => {
+            let _ = fs::remove_file(&installed_file);
+        }
+        false => {
+            let ids: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+            write_atomic(&installed_file, ids.join("\n").as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `--load-extension` flag for Chromium, quoted for the Exec key and empty
+/// without unpacked extensions.
+pub fn chromium_flag(extensions: &[PathBuf]) -> String {
+    let dirs: Vec<String> = extensions
+        .iter()
+        .filter(|path| is_unpacked(path))
+        .map(|path| path.to_string_lossy().to_string())
+        .filter(|dir| {
+            // Chromium splits the list on commas, there is no escaping them
+            let usable = !dir.contains(',');
+            if !usable {
+                tracing::warn!(
+                    "Skipping extension {}, Chromium cannot load paths with commas",
+                    dir
+                );
+            }
+            usable
+        })
+        .collect();
+
+    match dirs.is_empty() {
+        true => String::new(),
+        false => quote_exec_arg(&format!("--load-extension={}", dirs.join(","))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    use crate::common::split_exec;
+
+    use super::*;
+
+    // a package with the manifest behind another file, deflated like zip
+    // tools do
+    fn xpi(dir: &Path, manifest: &str) -> PathBuf {
+        let package = dir.join("package.xpi");
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut zip = ZipWriter::new(File::create(&package).unwrap());
+        zip.start_file("icons/icon.svg", options).unwrap();
+        zip.write_all(b"<svg/>").unwrap();
+        zip.start_file("manifest.json", options).unwrap();
+        zip.write_all(manifest.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        package
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quick-webapps-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn reads_add_on_id() {
+        let dir = temp_dir("xpi-id");
+
+        let path = xpi(
+            &dir,
+            r#"{"name": "Blocker", "browser_specific_settings": {"gecko": {"id": "blocker@example.com"}}}"#,
+        );
+        assert_eq!(xpi_id(&path).unwrap(), "blocker@example.com");
+
+        let path = xpi(&dir, r#"{"name": "Blocker"}"#);
+        assert!(xpi_id(&path).is_err());
+
+        fs::write(&path, b"not a zip").unwrap();
+        assert!(xpi_id(&path).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn installs_and_removes_our_extensions() {
+        let dir = temp_dir("xpi-install");
+        let profile = dir.join("profile");
+        let package = xpi(
+            &dir,
+            r#"{"applications": {"gecko": {"id": "blocker@example.com"}}}"#,
+        );
+        let installed = profile.join("extensions/blocker@example.com.xpi");
+        let foreign = profile.join("extensions/other@example.com.xpi");
+
+        install_firefox(&profile, std::slice::from_ref(&package)).unwrap();
+        assert_eq!(fs::read(&installed).unwrap(), fs::read(&package).unwrap());
+
+        fs::write(&foreign, b"").unwrap();
+        install_firefox(&profile, &[]).unwrap();
+        assert!(!installed.exists());
+        assert!(foreign.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn loads_unpacked_chromium_extensions() {
+        let dir = temp_dir("unpacked");
+        fs::write(dir.join("manifest.json"), "{}").unwrap();
+
+        assert!(check("chromium", &dir).is_ok());
+        assert!(check("firefox", &dir).is_err());
+        assert_eq!(
+            chromium_flag(&[dir.clone(), dir.join("missing")]),
+            format!("--load-extension={}", dir.display())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn quotes_extension_paths() {
+        let dir = temp_dir("unpacked dirs");
+        let spaced = dir.join("Dark Reader");
+        let comma = dir.join("a,b");
+
+        for extension in [&spaced, &comma] {
+            fs::create_dir_all(extension).unwrap();
+            fs::write(extension.join("manifest.json"), "{}").unwrap();
+        }
+
+        assert!(check("chromium", &comma).is_err());
+        let flag = chromium_flag(&[spaced.clone(), comma]);
+
+        assert_eq!(
+            split_exec(&flag),
+            [format!("--load-extension={}", spaced.display())]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
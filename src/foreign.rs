@@ -0,0 +1,718 @@
+use std::{
+    ffi::OsStr,
+    fmt,
+    fs::{self, create_dir_all, remove_dir_all, remove_file, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::{
+    common::{
+        desktop_filepath, get_supported_browsers, move_icon, split_exec, url_valid, write_atomic,
+        Browser, DesktopEntry, DesktopGroup, WebAppLauncher, DESKTOP_ENTRY_GROUP,
+    },
+    fl, paths,
+    supported_browsers::family_of,
+};
+
+// user data directories of Chromium based browsers, relative to ~/.config
+const CHROMIUM_CONFIG_DIRS: &[&str] = &[
+    "google-chrome",
+    "google-chrome-beta",
+    "google-chrome-unstable",
+    "chromium",
+    "microsoft-edge",
+    "microsoft-edge-beta",
+    "microsoft-edge-dev",
+    "BraveSoftware/Brave-Browser",
+    "vivaldi",
+];
+
+// Web App Manager and ICE keep a profile per app in these, below
+// ~/.local/share/ice
+const ICE_PROFILE_DIRS: &[&str] = &["firefox", "profiles"];
+
+// directory name prefixes of GNOME Web app profiles, current and older ones
+const EPIPHANY_PROFILE_PREFIXES: &[&str] =
+    &["org.gnome.Epiphany.WebApp", "epiphany-", "app-epiphany-"];
+
+// most of a web app database is compressed, so scanning it only finds a URL
+// now and then; more than this is not worth reading
+const MAX_SCANNED_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForeignSource {
+    WebAppManager,
+    Ice,
+    BrowserApp,
+    GnomeWeb,
+}
+
+impl fmt::Display for ForeignSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForeignSource::WebAppManager => write!(f, "Web App Manager"),
+            ForeignSource::Ice => write!(f, "Peppermint ICE"),
+            ForeignSource::BrowserApp => write!(f, "Browser app"),
+            ForeignSource::GnomeWeb => write!(f, "GNOME Web"),
+        }
+    }
+}
+
+/// A web app launcher created by another tool that we can take over.
+#[derive(Debug, Clone)]
+pub struct ForeignApp {
+    pub source: ForeignSource,
+    pub path: PathBuf,
+    pub name: String,
+    pub url: String,
+    pub icon: String,
+    pub category: String,
+    pub description: String,
+    /// Engine family of the browser the app was made for.
+    pub family: String,
+    /// Installed browser closest to the original one.
+    pub browser: Option<Browser>,
+    pub profile: Option<PathBuf>,
+    pub navbar: bool,
+    pub private: bool,
+    pub custom_parameters: String,
+}
+
+/// Tells which tool wrote a launcher, `None` for our own and unrelated files.
+pub fn source_of(path: &Path, entry: &DesktopGroup) -> Option<ForeignSource> {
+    let filename = path.file_name()?.to_str()?;
+    let exec = entry.get("Exec").unwrap_or_default();
+    let wm_class = entry.get("StartupWMClass").unwrap_or_default();
+
+    if entry.get("X-WebApp-Version").is_some() {
+        return None;
+    }
+
+    if filename.starts_with("webapp-") {
+        // same file names as ours, but profiles live in ~/.local/share/ice
+        // and Firefox is started through `sh -c`
+        if exec.contains("/ice/") || exec.contains("XAPP_FORCE_GTKWINDOW_ICON") {
+            return Some(ForeignSource::WebAppManager);
+        }
+        return None;
+    }
+
+    if wm_class.starts_with("ICE-SSB") || entry.get("X-ICE-SSB-Profile").is_some() {
+        Some(ForeignSource::Ice)
+    } else if filename.starts_with("org.gnome.Epiphany.WebApp")
+        || (filename.starts_with("epiphany-") && exec.contains("--application-mode"))
+    {
+        Some(ForeignSource::GnomeWeb)
+    } else if exec.contains("--app-id=") {
+        Some(ForeignSource::BrowserApp)
+    } else {
+        None
+    }
+}
+
+/// Exec without `sh -c` wrappers and leading environment assignments.
+pub fn program_args(exec: &str) -> Vec<String> {
+    let mut args = split_exec(exec);
+
+    let is_shell = args
+        .first()
+        .and_then(|program| Path::new(program).file_name())
+        .is_some_and(|name| name == "sh" || name == "bash");
+
+    // the script is single quoted, which desktop entries do not know about
+    if is_shell && args.len() > 2 && args[1] == "-c" {
+        args = split_exec(args[2..].join(" ").trim_matches('\''));
+    }
+
+    if args.first().is_some_and(|program| program == "env") {
+        args.remove(0);
+    }
+
+    let assignments = args
+        .iter()
+        .take_while(|arg| !arg.starts_with('-') && arg.contains('='))
+        .count();
+
+    args.split_off(assignments)
+}
+
+// flatpak exports are named after the application id
+pub fn program_name(args: &[String]) -> String {
+    let Some(program) = args.first() else {
+        return String::new();
+    };
+
+    let name = Path::new(program)
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+
+    if name == "flatpak" {
+        if let Some(id) = args
+            .iter()
+            .skip(1)
+            .find(|arg| !arg.starts_with('-') && arg.matches('.').count() >= 2)
+        {
+            return id.clone();
+        }
+    }
+
+    name.to_string()
+}
+
+pub fn family_from_args(args: &[String]) -> String {
+    let family = family_of(&program_name(args));
+
+    if !family.is_empty() {
+        return family.to_string();
+    }
+
+    let family = if args
+        .iter()
+        .any(|arg| arg.starts_with("--app=") || arg.starts_with("--user-data-dir"))
+    {
+        "chromium"
+    } else if args.iter().any(|arg| arg.starts_with("--profile")) {
+        "firefox"
+    } else {
+        ""
+    };
+
+    family.to_string()
+}
+
+fn match_browser(args: &[String], name: Option<String>, family: &str) -> Option<Browser> {
+    let installed: Vec<Browser> = get_supported_browsers()
+        .into_iter()
+        .filter(|browser| browser.is_installed())
+        .collect();
+    let program = program_name(args);
+
+    installed
+        .iter()
+        .find(|browser| {
+            !program.is_empty()
+                && Path::new(&browser.exec).file_name() == Some(OsStr::new(&program))
+        })
+        .or_else(|| name.and_then(|name| installed.iter().find(|browser| browser.name == name)))
+        .or_else(|| {
+            installed
+                .iter()
+                .find(|browser| !family.is_empty() && browser._type.family() == family)
+        })
+        .cloned()
+}
+
+fn url_from_args(args: &[String]) -> Option<String> {
+    args.iter()
+        .find_map(|arg| arg.strip_prefix("--app="))
+        .map(str::to_string)
+        .or_else(|| {
+            args.iter()
+                .rev()
+                .find(|arg| arg.starts_with("http") && url_valid(arg))
+                .cloned()
+        })
+}
+
+pub fn profile_from_args(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--profile" | "--user-data-dir" => args.next().cloned(),
+            _ => arg
+                .strip_prefix("--profile=")
+                .or_else(|| arg.strip_prefix("--user-data-dir="))
+                .map(str::to_string),
+        };
+
+        // Falkon takes a profile name here, not a directory
+        if let Some(path) = value.map(PathBuf::from).filter(|path| path.is_dir()) {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+// Looks for a URL stored shortly after the app id, in the binary records of
+// the browser's web app database. Only records that happen to be stored
+// uncompressed are found.
+fn find_url_near(data: &[u8], app_id: &str) -> Option<String> {
+    let needle = app_id.as_bytes();
+
+    for start in data
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| *window == needle)
+        .map(|(position, _)| position + needle.len())
+    {
+        let end = (start + 4096).min(data.len());
+        let window = &data[start..end];
+
+        let Some(offset) = window.windows(4).position(|bytes| bytes == b"http") else {
+            continue;
+        };
+
+        let url: String = window[offset..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_graphic() && !b"\"'<>\\".contains(byte))
+            .map(|byte| *byte as char)
+            .collect();
+
+        if url_valid(&url) {
+            return Some(url);
+        }
+    }
+
+    None
+}
+
+fn chromium_config_dirs() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = CHROMIUM_CONFIG_DIRS
+        .iter()
+        .map(|dir| paths::host_config_home().join(dir))
+        .collect();
+
+    if let Ok(apps) = fs::read_dir(paths::home_dir().join(".var/app")) {
+        for app in apps.flatten() {
+            roots.extend(
+                CHROMIUM_CONFIG_DIRS
+                    .iter()
+                    .map(|dir| app.path().join("config").join(dir)),
+            );
+        }
+    }
+
+    roots.into_iter().filter(|root| root.is_dir()).collect()
+}
+
+fn read_start(path: &Path) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+
+    File::open(path)
+        .ok()?
+        .take(MAX_SCANNED_BYTES)
+        .read_to_end(&mut data)
+        .ok()?;
+
+    Some(data)
+}
+
+// "install as app" launchers only carry an app id, the URL stays in the profile
+fn browser_app_url(args: &[String]) -> Option<String> {
+    let app_id = args.iter().find_map(|arg| arg.strip_prefix("--app-id="))?;
+    let profile = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--profile-directory="))
+        .unwrap_or("Default");
+    let profile_dirs: Vec<PathBuf> = chromium_config_dirs()
+        .into_iter()
+        .map(|root| root.join(profile))
+        .collect();
+
+    // older bookmark apps keep their manifest in Preferences
+    for profile_dir in &profile_dirs {
+        if let Some(url) = fs::read_to_string(profile_dir.join("Preferences"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .and_then(|prefs| {
+                prefs
+                    .pointer(&format!(
+                        "/extensions/settings/{}/manifest/app/launch/web_url",
+                        app_id
+                    ))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+        {
+            return Some(url);
+        }
+    }
+
+    // newer ones only have the web app database, worth a try at least
+    profile_dirs
+        .iter()
+        .flat_map(|profile_dir| {
+            [
+                profile_dir.join("Web Applications"),
+                profile_dir.join("Sync Data/LevelDB"),
+            ]
+        })
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .find_map(|file| find_url_near(&read_start(&file)?, app_id))
+}
+
+// a browser's own profiles, which adopting an app must never take away
+fn is_browser_root(path: &Path) -> bool {
+    let mut defaults: Vec<PathBuf> = CHROMIUM_CONFIG_DIRS
+        .iter()
+        .map(|dir| paths::host_config_home().join(dir))
+        .collect();
+    defaults.push(paths::home_dir().join(".mozilla"));
+    defaults.push(paths::host_config_home().join("epiphany"));
+
+    defaults
+        .iter()
+        .any(|root| root.starts_with(path) || path.starts_with(root))
+        || get_supported_browsers()
+            .iter()
+            .any(|browser| browser.profile_root().starts_with(path))
+}
+
+/// Whether `profile` is one the tool behind `source` made for a single app.
+/// Other directories named in a launcher are left alone, they may well be
+/// the user's main browser profile.
+fn made_by(source: ForeignSource, profile: &Path) -> bool {
+    let Some(name) = profile.file_name().and_then(OsStr::to_str) else {
+        return false;
+    };
+
+    let owned = match source {
+        ForeignSource::WebAppManager | ForeignSource::Ice => {
+            let ice = paths::host_data_home().join("ice");
+
+            ICE_PROFILE_DIRS
+                .iter()
+                .any(|dir| profile.parent() == Some(ice.join(dir).as_path()))
+        }
+        ForeignSource::GnomeWeb => EPIPHANY_PROFILE_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix)),
+        ForeignSource::BrowserApp => false,
+    };
+
+    owned && !is_browser_root(profile)
+}
+
+// theme icons are looked up in hicolor, taking the largest one available
+fn resolve_icon(icon: &str) -> Option<PathBuf> {
+    let path = Path::new(icon);
+
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_path_buf());
+    }
+
+    let mut data_dirs = vec![paths::host_data_home()];
+    data_dirs.extend(paths::data_dirs());
+
+    let mut best: Option<(u32, PathBuf)> = None;
+
+    for sizes in data_dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir.join("icons/hicolor")).ok())
+        .flat_map(|entries| entries.flatten())
+    {
+        let size_name = sizes.file_name().to_string_lossy().to_string();
+        let size = match size_name.as_str() {
+            "scalable" => u32::MAX,
+            name => name
+                .split('x')
+                .next()
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(0),
+        };
+
+        for extension in ["svg", "png"] {
+            let candidate = sizes
+                .path()
+                .join("apps")
+                .join(format!("{}.{}", icon, extension));
+
+            if candidate.exists() && !matches!(best, Some((best_size, _)) if best_size >= size) {
+                best = Some((size, candidate));
+            }
+        }
+    }
+
+    best.map(|(_, path)| path)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+
+        if entry.file_type().is_dir() {
+            create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves profile data, refusing to mix it with an existing profile.
+pub fn move_profile_dir(from: &Path, target: &Path) -> Result<()> {
+    if target.exists() && fs::read_dir(target)?.next().is_some() {
+        return Err(anyhow!("Profile directory {:?} is not empty.", target));
+    }
+
+    if let Some(parent) = target.parent() {
+        create_dir_all(parent)?;
+    }
+    let _ = fs::remove_dir(target);
+
+    // rename fails across file systems, e.g. into a Flatpak data dir
+    if fs::rename(from, target).is_err() {
+        copy_dir(from, target)?;
+        remove_dir_all(from)?;
+    }
+
+    tracing::info!("Moved profile from {:?} to {:?}", from, target);
+
+    Ok(())
+}
+
+fn application_dirs() -> Vec<PathBuf> {
+    vec![
+        desktop_filepath(""),
+        // launchers installed through the dynamic launcher portal
+        paths::host_data_home().join("xdg-desktop-portal/applications"),
+    ]
+}
+
+impl ForeignApp {
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let desktop_entry = DesktopEntry::read(path)?;
+
+        let Some(entry) = desktop_entry.group(DESKTOP_ENTRY_GROUP) else {
+            return Ok(None);
+        };
+
+        let Some(source) = source_of(path, entry) else {
+            return Ok(None);
+        };
+
+        let args = program_args(&entry.get("Exec").unwrap_or_default());
+
+        let url = entry
+            .get("X-WebApp-URL")
+            .filter(|url| !url.is_empty())
+            .or_else(|| url_from_args(&args))
+            .or_else(|| match source {
+                ForeignSource::BrowserApp => browser_app_url(&args),
+                _ => None,
+            });
+
+        let original = match_browser(&args, entry.get("X-WebApp-Browser"), "");
+        let family = original
+            .as_ref()
+            .map(|browser| browser._type.family().to_string())
+            .unwrap_or_else(|| family_from_args(&args));
+        let browser = original.or_else(|| match_browser(&[], None, &family));
+
+        let category = entry
+            .get_list("Categories")
+            .into_iter()
+            .find(|category| category != "GTK")
+            .unwrap_or_else(|| fl!("web"));
+
+        Ok(Some(Self {
+            source,
+            path: path.to_path_buf(),
+            name: entry.get("Name").unwrap_or_default(),
+            url: url.unwrap_or_default(),
+            icon: entry.get("Icon").unwrap_or_default(),
+            category,
+            description: entry.get("Comment").unwrap_or_default(),
+            family,
+            browser,
+            profile: profile_from_args(&args),
+            navbar: entry.get_bool("X-WebApp-Navbar").unwrap_or(false),
+            private: entry.get_bool("X-WebApp-PrivateWindow").unwrap_or(false),
+            custom_parameters: entry.get("X-WebApp-CustomParameters").unwrap_or_default(),
+        }))
+    }
+
+    fn move_profile(&self, from: &Path, launcher: &WebAppLauncher) -> Result<()> {
+        if !made_by(self.source, from) {
+            return Err(anyhow!(
+                "{} is not a profile {} made for {}, it stays where it is.",
+                from.display(),
+                self.source,
+                self.name
+            ));
+        }
+
+        if launcher.web_browser._type.family() != self.family {
+            return Err(anyhow!(
+                "Profile data of {} cannot be used by {}.",
+                self.name,
+                launcher.web_browser.name
+            ));
+        }
+
+        move_profile_dir(from, &launcher.profile_dir())
+    }
+
+    // the original launcher is kept aside in case the user wants it back
+    fn retire(&self) -> Result<()> {
+        let adopted = paths::app_data_dir().join("adopted");
+        create_dir_all(&adopted)?;
+
+        let filename = self
+            .path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid launcher path {:?}.", self.path))?;
+
+        write_atomic(&adopted.join(filename), &fs::read(&self.path)?)?;
+        remove_file(&self.path)?;
+
+        Ok(())
+    }
+
+    /// Converts the app into one of ours, optionally carrying its profile over.
+    pub fn adopt(&self, browser: Option<Browser>, move_profile: bool) -> Result<WebAppLauncher> {
+        let browser = browser.or_else(|| self.browser.clone()).ok_or_else(|| {
+            anyhow!(
+                "No installed browser matches {}, please choose one.",
+                self.name
+            )
+        })?;
+
+        if !url_valid(&self.url) {
+            return Err(anyhow!("Cannot find the URL of {}.", self.name));
+        }
+
+        // Web App Manager launchers already use our file names, keep them
+        let codename = match self.source {
+            ForeignSource::WebAppManager => self
+                .path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .and_then(|stem| stem.strip_prefix("webapp-"))
+                .map(str::to_string),
+            _ => None,
+        };
+
+        let icon = match resolve_icon(&self.icon) {
+            Some(path) => move_icon(path.to_string_lossy().to_string(), self.name.clone()),
+            None => self.icon.clone(),
+        };
+
+        let mut launcher = WebAppLauncher::new(
+            self.name.clone(),
+            codename,
+            self.url.clone(),
+            icon,
+            self.category.clone(),
+            browser,
+            self.custom_parameters.clone(),
+            self.profile.is_some(),
+            self.navbar,
+            self.private,
+        );
+
+        launcher.description.clone_from(&self.description);
+
+        if !launcher.is_valid {
+            return Err(anyhow!(
+                "Web App invalid. Maybe you already have this Web App?"
+            ));
+        }
+
+        // writing the launcher seeds the profile, so it has to be in place first
+        let moved = match (&self.profile, move_profile) {
+            (Some(profile), true) => {
+                self.move_profile(profile, &launcher)?;
+                Some(profile)
+            }
+            _ => None,
+        };
+
+        if let Err(e) = launcher.create() {
+            // the original launcher still points at the old place
+            if let Some(profile) = moved {
+                if let Err(e) = move_profile_dir(&launcher.profile_dir(), profile) {
+                    tracing::error!("Cannot move the profile of {} back: {}", self.name, e);
+                }
+            }
+
+            return Err(e);
+        }
+
+        if self.path != launcher.path {
+            self.retire()?;
+        }
+
+        Ok(launcher)
+    }
+}
+
+pub fn find_foreign_apps() -> Vec<ForeignApp> {
+    let mut apps = Vec::new();
+
+    for entries in application_dirs()
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+    {
+        for path in entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some(OsStr::new("desktop")))
+        {
+            match ForeignApp::read(&path) {
+                Ok(Some(app)) => apps.push(app),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Cannot read {:?}: {}", path, e),
+            }
+        }
+    }
+
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+    apps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_only_profiles_made_for_the_app() {
+        let ice = paths::host_data_home().join("ice");
+        let config = paths::host_config_home();
+
+        assert!(made_by(ForeignSource::Ice, &ice.join("profiles/mail")));
+        assert!(made_by(
+            ForeignSource::WebAppManager,
+            &ice.join("firefox/mail")
+        ));
+        assert!(!made_by(ForeignSource::WebAppManager, &ice.join("firefox")));
+        assert!(!made_by(ForeignSource::Ice, &config.join("chromium")));
+        assert!(!made_by(
+            ForeignSource::WebAppManager,
+            &paths::home_dir().join(".mozilla/firefox/abcd.default")
+        ));
+        assert!(!made_by(
+            ForeignSource::BrowserApp,
+            &config.join("google-chrome")
+        ));
+        assert!(made_by(
+            ForeignSource::GnomeWeb,
+            &paths::data_home().join("org.gnome.Epiphany.WebApp_mail-1234")
+        ));
+    }
+
+    #[test]
+    fn finds_urls_after_the_app_id() {
+        let data = b"\x00\x01abcdefgh\x12\x1ahttps://mail.example.com/inbox\x22\x00other";
+
+        assert_eq!(
+            find_url_near(data, "abcdefgh").as_deref(),
+            Some("https://mail.example.com/inbox")
+        );
+        assert_eq!(find_url_near(data, "ijklmnop"), None);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{common::DesktopEntry, version::Version};
+
+/// A Firefox based browser, as described by the `application.ini` and
+/// `platform.ini` it ships next to its binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeckoApp {
+    pub name: String,
+    pub remoting_name: String,
+    pub version: Option<Version>,
+    /// Gecko release the browser is built on. Prefs and flags follow it,
+    /// while forks like Waterfox number their own releases differently.
+    pub platform_version: Option<Version>,
+}
+
+impl GeckoApp {
+    pub fn read(dir: &Path) -> Option<Self> {
+        let application = DesktopEntry::read(&dir.join("application.ini")).ok()?;
+        let app = application.group("App")?;
+
+        let platform_version = DesktopEntry::read(&dir.join("platform.ini"))
+            .ok()
+            .and_then(|platform| platform.group("Build")?.get("Milestone"))
+            .and_then(|milestone| Version::parse(&milestone));
+
+        Some(Self {
+            name: app.get("Name")?,
+            remoting_name: app.get("RemotingName").unwrap_or_default(),
+            version: app
+                .get("Version")
+                .and_then(|version| Version::parse(&version)),
+            platform_version,
+        })
+    }
+
+    /// Looks next to the real binary first, since `exec` is often a symlink
+    /// or a wrapper script in `/usr/bin`.
+    pub fn find(exec: &Path) -> Option<Self> {
+        let target = fs::canonicalize(exec).ok()?;
+        let name = target.file_name()?.to_str()?.trim_end_matches(".sh");
+
+        let mut dirs: Vec<PathBuf> = target.parent().into_iter().map(Path::to_path_buf).collect();
+        dirs.extend(
+            ["/usr/lib", "/usr/lib64", "/opt"]
+                .iter()
+                .map(|root| Path::new(root).join(name)),
+        );
+
+        dirs.iter().find_map(|dir| Self::read(dir))
+    }
+
+    /// Files of a Flatpak, deployed below its installation.
+    pub fn find_flatpak(installation: &Path, id: &str) -> Option<Self> {
+        let files = installation
+            .join("app")
+            .join(id)
+            .join("current/active/files");
+
+        fs::read_dir(files.join("lib"))
+            .ok()?
+            .flatten()
+            .find_map(|dir| Self::read(&dir.path()))
+    }
+
+    /// Engine version when known, the release otherwise.
+    pub fn engine_version(&self) -> Option<Version> {
+        self.platform_version.clone().or(self.version.clone())
+    }
+
+    /// Directory name for profiles of this browser. Stock Firefox answers to
+    /// `firefox`, the root every Firefox profile used to share, and forks
+    /// get their own as their profile schemas differ.
+    pub fn profile_key(&self) -> String {
+        let key = match self.remoting_name.is_empty() {
+            true => &self.name,
+            false => &self.remoting_name,
+        };
+
+        key.to_lowercase()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect()
+    }
+
+    /// Whether `path` is the browser itself rather than one of the helpers
+    /// installed next to it, like `updater` or `crashreporter`.
+    pub fn is_main_binary(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name == self.profile_key() || name.eq_ignore_ascii_case(&self.name))
+    }
+}
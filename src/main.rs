@@ -1,8 +1,23 @@
+mod bundle;
+mod chromium;
+mod cli;
+mod codename;
 mod common;
+mod extensions;
+mod foreign;
+mod gecko;
 mod icon_cache;
 mod localize;
+mod migration;
+mod orphan;
 mod pages;
+mod paths;
+mod prefs;
+mod profiles;
+mod sandbox;
 mod supported_browsers;
+mod userchrome;
+mod version;
 mod warning;
 
 use std::{os::unix::fs::PermissionsExt, process::ExitStatus};
@@ -19,12 +34,21 @@ use pages::Window;
 fn main() -> cosmic::iced::Result {
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::INFO)
+        .with_writer(std::io::stderr)
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
     init_localizer();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
+
+    migration::run();
+
     let mut settings = Settings::default();
     settings = settings.size(Size {
         width: 700.,
@@ -61,10 +85,15 @@ pub fn icon_pack_installed() -> bool {
 
 pub async fn add_icon_packs_install_script() -> String {
     let install_script = include_bytes!("../scripts/icon-installer.sh");
-    let temp_file = "/tmp/io.github.elevenhsoft.WebApps.icon-installer.sh";
+    let mut temp_dir = paths::app_cache_dir();
+
+    // Create a temporary file, in /tmp when the cache cannot be written
+    if tokio::fs::create_dir_all(&temp_dir).await.is_err() {
+        temp_dir = std::env::temp_dir();
+    }
+    let temp_file = temp_dir.join("icon-installer.sh");
 
-    // Create a temporary file
-    let mut file = File::create(temp_file).await.unwrap();
+    let mut file = File::create(&temp_file).await.unwrap();
 
     file.write_all(install_script).await.unwrap();
 
@@ -73,11 +102,12 @@ pub async fn add_icon_packs_install_script() -> String {
     perms.set_mode(0o755);
     file.set_permissions(perms).await.unwrap();
 
-    temp_file.to_string()
+    temp_file.to_string_lossy().to_string()
 }
 
 pub async fn execute_script(script: String) -> ExitStatus {
     tokio::process::Command::new(script)
+        .env("LOCAL_DESTDIR", icons_location())
         .status()
         .await
         .expect("cant execute script")
@@ -0,0 +1,479 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    common::{
+        backup_launcher, desktop_filepath, get_supported_browsers, launcher_version, move_icon,
+        my_icons_location, split_exec, url_valid, DesktopEntry, DesktopGroup, WebAppLauncher,
+        DESKTOP_ENTRY_GROUP, LAUNCHER_VERSION,
+    },
+    foreign::{move_profile_dir, source_of},
+    paths,
+    profiles::rebase_profiles,
+};
+
+// every Firefox based browser used to keep its profiles here
+const SHARED_FIREFOX_ROOT: &str = ".local/share/quick-webapps/firefox";
+
+// keys compared before and after a migration to describe what changed
+const REPORTED_KEYS: &[&str] = &[
+    "Name",
+    "Comment",
+    "Exec",
+    "Icon",
+    "Categories",
+    "StartupWMClass",
+    "Actions",
+    "X-WebApp-Browser",
+    "X-WebApp-URL",
+    "X-WebApp-Navbar",
+    "X-WebApp-PrivateWindow",
+    "X-WebApp-Isolated",
+    "X-WebApp-CustomParameters",
+];
+
+type Step = fn(&mut DesktopGroup);
+
+// STEPS[n] upgrades a launcher from version n to n + 1
+const STEPS: &[Step] = &[to_v1];
+
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub path: PathBuf,
+    pub changes: Vec<String>,
+}
+
+// Launchers from before versioning may lack the X-WebApp keys entirely, so
+// recover what we can from Exec.
+fn to_v1(entry: &mut DesktopGroup) {
+    let args = split_exec(&entry.get("Exec").unwrap_or_default());
+    let has_arg = |flags: &[&str]| {
+        args.iter()
+            .any(|arg| flags.iter().any(|flag| arg.starts_with(flag)))
+    };
+
+    if entry.get("X-WebApp-URL").is_none() {
+        let url = args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("--app="))
+            .map(str::to_string)
+            .or_else(|| args.iter().rev().find(|arg| url_valid(arg)).cloned());
+
+        if let Some(url) = url {
+            entry.set("X-WebApp-URL", &url);
+        }
+    }
+
+    if entry.get("X-WebApp-Browser").is_none() {
+        if let Some(browser) = args.first().and_then(|exec| {
+            get_supported_browsers()
+                .into_iter()
+                .find(|browser| &browser.exec == exec)
+        }) {
+            entry.set("X-WebApp-Browser", &browser.name);
+        }
+    }
+
+    if entry.get("X-WebApp-Isolated").is_none() {
+        entry.set_bool(
+            "X-WebApp-Isolated",
+            has_arg(&["--user-data-dir", "--profile"]),
+        );
+    }
+
+    if entry.get("X-WebApp-PrivateWindow").is_none() {
+        entry.set_bool(
+            "X-WebApp-PrivateWindow",
+            has_arg(&[
+                "--private-window",
+                "--incognito",
+                "--inprivate",
+                "--private-browsing",
+            ]),
+        );
+    }
+
+    if entry.get("X-WebApp-Navbar").is_none() {
+        entry.set_bool("X-WebApp-Navbar", false);
+    }
+
+    if entry.get("X-WebApp-CustomParameters").is_none() {
+        entry.set("X-WebApp-CustomParameters", "");
+    }
+}
+
+// icons picked from elsewhere on disk are copied next to the ones we manage,
+// so the launcher keeps working when the original goes away
+fn relocate_icon(launcher: &mut WebAppLauncher) {
+    let icon_path = Path::new(&launcher.icon);
+
+    if icon_path.is_absolute() && icon_path.exists() && !icon_path.starts_with(my_icons_location())
+    {
+        launcher.icon = move_icon(launcher.icon.clone(), launcher.name.clone());
+    }
+}
+
+fn describe_changes(before: &DesktopEntry, after: &DesktopEntry) -> Vec<String> {
+    let before = before.group(DESKTOP_ENTRY_GROUP);
+    let after = after.group(DESKTOP_ENTRY_GROUP);
+    let value = |group: Option<&DesktopGroup>, key: &str| group.and_then(|g| g.get(key));
+
+    REPORTED_KEYS
+        .iter()
+        .filter_map(|key| match (value(before, key), value(after, key)) {
+            (None, Some(new)) => Some(format!("added {}={}", key, new)),
+            (Some(_), None) => Some(format!("removed {}", key)),
+            (Some(old), Some(new)) if old != new => {
+                Some(format!("changed {}: {} -> {}", key, old, new))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Upgrades a single launcher in place. Returns `None` when it is already
+/// current, or was written by a newer version that we should not touch.
+pub fn migrate_launcher(path: &Path, codename: &str) -> Result<Option<MigrationReport>> {
+    let original = DesktopEntry::read(path)?;
+    let entry = original
+        .group(DESKTOP_ENTRY_GROUP)
+        .ok_or_else(|| anyhow!("Missing [{}] group.", DESKTOP_ENTRY_GROUP))?;
+    let from_version = launcher_version(entry);
+
+    // launchers of other managers are converted on request, see foreign.rs
+    if from_version >= LAUNCHER_VERSION || source_of(path, entry).is_some() {
+        return Ok(None);
+    }
+
+    let mut desktop_entry = original.clone();
+    let entry = desktop_entry.group_mut(DESKTOP_ENTRY_GROUP);
+
+    for step in &STEPS[from_version as usize..] {
+        step(entry);
+    }
+
+    // regenerating the launcher brings Exec and actions to the current flags
+    let mut launcher =
+        WebAppLauncher::from_entry(path.to_path_buf(), codename.to_string(), desktop_entry)?;
+    launcher.exec.clone_from(&launcher.web_browser.exec);
+    relocate_icon(&mut launcher);
+
+    let migrated = launcher.to_desktop_entry();
+
+    let mut changes = describe_changes(&original, &migrated);
+    changes.push(format!(
+        "upgraded from version {} to {}",
+        from_version, LAUNCHER_VERSION
+    ));
+
+    // the profile catches up with the settings of the current version too
+    if let Err(e) = launcher.prepare_profile() {
+        tracing::warn!("Cannot prepare profile of {}: {}", codename, e);
+    }
+
+    backup_launcher(codename, path)?;
+    migrated.write(path)?;
+
+    Ok(Some(MigrationReport {
+        path: path.to_path_buf(),
+        changes,
+    }))
+}
+
+/// Moves the profile of a Firefox fork out of the root all forks used to
+/// share, so that another fork can never open it.
+pub fn relocate_fork_profile(path: &Path, codename: &str) -> Result<Option<MigrationReport>> {
+    let shared = paths::resolve(SHARED_FIREFOX_ROOT).join(codename);
+
+    // reading the launcher looks up its browser, not worth it when there is
+    // nothing to move
+    if !shared.is_dir() {
+        return Ok(None);
+    }
+
+    let mut launcher = WebAppLauncher::read(path.to_path_buf(), codename.to_string())?;
+    let target = launcher.profile_dir();
+
+    if launcher.web_browser._type.family() != "firefox"
+        || !launcher.isolate_profile
+        || launcher.profile_location.is_some()
+        || target == shared
+        || target.exists()
+    {
+        return Ok(None);
+    }
+
+    move_profile_dir(&shared, &target)?;
+
+    launcher.exec.clone_from(&launcher.web_browser.exec);
+    backup_launcher(codename, path)?;
+    launcher.to_desktop_entry().write(path)?;
+
+    Ok(Some(MigrationReport {
+        path: path.to_path_buf(),
+        changes: vec![format!(
+            "moved profile from {} to {}",
+            shared.display(),
+            target.display()
+        )],
+    }))
+}
+
+// moves what it can from `from` into `to`, leaving anything already there
+fn merge_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)?.flatten() {
+        let target = to.join(entry.file_name());
+
+        if target.exists() {
+            tracing::warn!(
+                "Not moving {}, {} already exists",
+                entry.path().display(),
+                target.display()
+            );
+            continue;
+        }
+
+        match entry.path().is_dir() {
+            true => move_profile_dir(&entry.path(), &target)?,
+            false => {
+                if fs::rename(entry.path(), &target).is_err() {
+                    fs::copy(entry.path(), &target)?;
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
+    }
+
+    // only goes away when everything could be moved
+    let _ = fs::remove_dir(from);
+
+    Ok(())
+}
+
+// Exec and X-WebApp-Profile of launchers written before the move still
+// name the old directory
+fn rebase_launcher(path: &Path, codename: &str, from: &Path, to: &Path) -> Result<bool> {
+    let mut desktop_entry = DesktopEntry::read(path)?;
+    let entry = desktop_entry.group_mut(DESKTOP_ENTRY_GROUP);
+    let from_str = from.to_string_lossy();
+
+    let exec = entry.get("Exec").unwrap_or_default();
+    let location = entry.get("X-WebApp-Profile").map(PathBuf::from);
+
+    if !exec.contains(from_str.as_ref())
+        && !location.as_ref().is_some_and(|path| path.starts_with(from))
+    {
+        return Ok(false);
+    }
+
+    if let Some(rest) = location
+        .as_ref()
+        .and_then(|path| path.strip_prefix(from).ok())
+    {
+        entry.set("X-WebApp-Profile", &to.join(rest).to_string_lossy());
+    }
+
+    // outdated launchers get their Exec from migrate_launcher right after
+    if launcher_version(entry) < LAUNCHER_VERSION {
+        backup_launcher(codename, path)?;
+        desktop_entry.write(path)?;
+    } else {
+        let mut launcher =
+            WebAppLauncher::from_entry(path.to_path_buf(), codename.to_string(), desktop_entry)?;
+        launcher.exec.clone_from(&launcher.web_browser.exec);
+        launcher.create()?;
+    }
+
+    Ok(true)
+}
+
+/// Moves profiles, backups and shared profiles from `~/.local/share` into
+/// `XDG_DATA_HOME` when the two differ, and points launchers to the new
+/// place.
+pub fn migrate_data_dir() -> Vec<MigrationReport> {
+    let from = paths::legacy_app_data_dir();
+    let to = paths::app_data_dir();
+
+    if from == to || !from.is_dir() {
+        return Vec::new();
+    }
+
+    if let Err(e) = merge_dir(&from, &to) {
+        tracing::warn!("Cannot move {}: {}", from.display(), e);
+        return Vec::new();
+    }
+
+    if let Err(e) = rebase_profiles(&from, &to) {
+        tracing::warn!("Cannot update shared profiles: {}", e);
+    }
+
+    let mut reports = Vec::new();
+
+    for (path, codename) in launchers() {
+        match rebase_launcher(&path, &codename, &from, &to) {
+            Ok(true) => reports.push(MigrationReport {
+                path,
+                changes: vec![format!(
+                    "moved data from {} to {}",
+                    from.display(),
+                    to.display()
+                )],
+            }),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Cannot update {}: {}", path.display(), e),
+        }
+    }
+
+    reports
+}
+
+// every `webapp-*.desktop` launcher with its codename
+fn launchers() -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir(desktop_filepath("")) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let codename = filename
+                .strip_prefix("webapp-")?
+                .strip_suffix(".desktop")?
+                .to_string();
+
+            Some((entry.path(), codename))
+        })
+        .collect()
+}
+
+/// Runs over every `webapp-*.desktop` launcher. Launchers that are current
+/// are only read, so this is cheap once everything was migrated.
+pub fn migrate_launchers() -> Vec<MigrationReport> {
+    let mut reports = Vec::new();
+
+    for (path, codename) in launchers() {
+        match migrate_launcher(&path, &codename) {
+            Ok(Some(report)) => reports.push(report),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Cannot migrate {}: {}", path.display(), e),
+        }
+
+        match relocate_fork_profile(&path, &codename) {
+            Ok(Some(report)) => reports.push(report),
+            Ok(None) => {}
+            Err(e) => tracing::debug!("Cannot relocate profile of {}: {}", path.display(), e),
+        }
+    }
+
+    reports
+}
+
+/// Brings the data directory and every launcher up to date, logging what
+/// changed. Runs when the window opens and before commands that write
+/// launchers.
+pub fn run() {
+    let reports = migrate_data_dir().into_iter().chain(migrate_launchers());
+
+    for report in reports {
+        for change in &report.changes {
+            tracing::info!("Migrated {}: {}", report.path.display(), change);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_group(exec: &str) -> DesktopGroup {
+        let content = format!(
+            "[Desktop Entry]\nName=Mail\nExec={}\nX-WebApp-Browser=Firefox\n",
+            exec
+        );
+
+        DesktopEntry::parse(&content)
+            .group(DESKTOP_ENTRY_GROUP)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn recovers_keys_from_firefox_exec() {
+        let mut entry = legacy_group(
+            "firefox --class WebApp-mail --profile /home/u/.local/share/quick-webapps/firefox/mail --no-remote --private-window https://mail.example.com",
+        );
+
+        to_v1(&mut entry);
+
+        assert_eq!(
+            entry.get("X-WebApp-URL").unwrap(),
+            "https://mail.example.com"
+        );
+        assert_eq!(entry.get("X-WebApp-Browser").unwrap(), "Firefox");
+        assert_eq!(entry.get_bool("X-WebApp-Isolated"), Some(true));
+        assert_eq!(entry.get_bool("X-WebApp-PrivateWindow"), Some(true));
+        assert_eq!(entry.get_bool("X-WebApp-Navbar"), Some(false));
+        assert_eq!(entry.get("X-WebApp-CustomParameters").unwrap(), "");
+    }
+
+    #[test]
+    fn recovers_keys_from_chromium_exec() {
+        let mut entry = legacy_group(
+            "chromium --app=https://chat.example.com --class=WebApp-chat --name=WebApp-chat",
+        );
+        entry.set_bool("X-WebApp-Navbar", true);
+
+        to_v1(&mut entry);
+
+        assert_eq!(
+            entry.get("X-WebApp-URL").unwrap(),
+            "https://chat.example.com"
+        );
+        assert_eq!(entry.get_bool("X-WebApp-Isolated"), Some(false));
+        assert_eq!(entry.get_bool("X-WebApp-PrivateWindow"), Some(false));
+        // keys that are there already are kept
+        assert_eq!(entry.get_bool("X-WebApp-Navbar"), Some(true));
+    }
+
+    #[test]
+    fn describes_changed_keys() {
+        let before = DesktopEntry::parse(
+            "[Desktop Entry]\nName=Mail\nExec=firefox https://mail.example.com\nIcon=mail\n",
+        );
+        let after = DesktopEntry::parse(
+            "[Desktop Entry]\nName=Mail\nExec=firefox --class WebApp-mail https://mail.example.com\nX-WebApp-URL=https://mail.example.com\n",
+        );
+
+        assert_eq!(
+            describe_changes(&before, &after),
+            vec![
+                "changed Exec: firefox https://mail.example.com -> firefox --class WebApp-mail https://mail.example.com",
+                "removed Icon",
+                "added X-WebApp-URL=https://mail.example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_current_launchers_alone() {
+        let dir =
+            std::env::temp_dir().join(format!("quick-webapps-migration-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("webapp-mail.desktop");
+        let content = "[Desktop Entry]\nName=Mail\nExec=firefox https://mail.example.com\nX-WebApp-Version=1\n";
+        fs::write(&path, content).unwrap();
+
+        assert!(migrate_launcher(&path, "mail").unwrap().is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
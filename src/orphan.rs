@@ -0,0 +1,179 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    common::{
+        desktop_filepath, get_supported_browsers, Browser, DesktopEntry, WebAppLauncher,
+        DESKTOP_ENTRY_GROUP,
+    },
+    foreign::{
+        family_from_args, move_profile_dir, profile_from_args, program_args, program_name,
+        source_of,
+    },
+};
+
+/// One of our web apps whose browser is uninstalled, or known under another
+/// name since the launcher was written.
+#[derive(Debug, Clone)]
+pub struct OrphanedApp {
+    pub path: PathBuf,
+    pub codename: String,
+    pub name: String,
+    /// Browser name from `X-WebApp-Browser`.
+    pub browser_name: String,
+    /// Engine family of the missing browser, guessed from `Exec`.
+    pub family: String,
+    pub profile: Option<PathBuf>,
+    program: String,
+    desktop_entry: DesktopEntry,
+}
+
+impl OrphanedApp {
+    /// Returns `None` for launchers whose browser is still there and for
+    /// launchers that are not ours.
+    pub fn read(path: &Path, codename: &str) -> Result<Option<Self>> {
+        let desktop_entry = DesktopEntry::read(path)?;
+
+        let Some(entry) = desktop_entry.group(DESKTOP_ENTRY_GROUP) else {
+            return Ok(None);
+        };
+
+        if source_of(path, entry).is_some() {
+            return Ok(None);
+        }
+
+        let browser_name = entry.get("X-WebApp-Browser").unwrap_or_default();
+
+        if Browser::web_browser(browser_name.clone()).is_some() {
+            return Ok(None);
+        }
+
+        let args = program_args(&entry.get("Exec").unwrap_or_default());
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            codename: codename.to_string(),
+            name: entry.get("Name").unwrap_or_default(),
+            family: family_from_args(&args),
+            profile: profile_from_args(&args),
+            program: program_name(&args),
+            browser_name,
+            desktop_entry,
+        }))
+    }
+
+    /// Installed browsers the app can move to, best match first. A browser
+    /// with the same executable is most likely the old one under a new name.
+    pub fn candidates(&self) -> Vec<Browser> {
+        let mut candidates: Vec<Browser> = get_supported_browsers()
+            .into_iter()
+            .filter(|browser| {
+                browser.is_installed()
+                    && (self.family.is_empty() || browser._type.family() == self.family)
+            })
+            .collect();
+
+        candidates.sort_by_key(|browser| {
+            Path::new(&browser.exec).file_name() != Some(OsStr::new(&self.program))
+        });
+
+        candidates
+    }
+
+    /// Points the launcher at another browser. Profile data is kept when the
+    /// new browser uses the same engine and moved if its profile root differs.
+    /// A shared profile the new browser cannot open is left to its other apps.
+    pub fn rebind(&self, browser: &Browser) -> Result<WebAppLauncher> {
+        let family = browser._type.family();
+
+        if !self.family.is_empty() && family != self.family {
+            return Err(anyhow!(
+                "{} cannot take over {}, it was made for a {} browser.",
+                browser.name,
+                self.name,
+                self.family
+            ));
+        }
+
+        let mut desktop_entry = self.desktop_entry.clone();
+        let entry = desktop_entry.group_mut(DESKTOP_ENTRY_GROUP);
+        entry.set("X-WebApp-Browser", &browser.name);
+        // a profile moved into a Flatpak sandbox belongs to that Flatpak
+        entry.remove("X-WebApp-Profile");
+
+        let mut launcher =
+            WebAppLauncher::from_entry(self.path.clone(), self.codename.clone(), desktop_entry)?;
+        launcher.exec.clone_from(&launcher.web_browser.exec);
+
+        // a shared profile stays with the apps of its browser, the app gets
+        // a profile of its own instead
+        let shared = launcher.shared();
+
+        if let Some(profile) = &shared {
+            if let Err(e) = profile.check(browser) {
+                tracing::warn!("Leaving profile {} behind: {}", profile.name, e);
+                launcher.shared_profile = None;
+            }
+        }
+
+        if let (Some(profile), None) = (&self.profile, &shared) {
+            let target = launcher.profile_dir();
+
+            if launcher.isolate_profile && family == self.family && *profile != target {
+                move_profile_dir(profile, &target)?;
+            }
+        }
+
+        launcher.create()?;
+
+        tracing::info!("Moved {} to {}", self.codename, browser.name);
+
+        Ok(launcher)
+    }
+
+    /// Rebinds to the best candidate, see `candidates`.
+    pub fn rebind_to_best(&self) -> Result<WebAppLauncher> {
+        let browser = self.candidates().into_iter().next().ok_or_else(|| {
+            anyhow!(
+                "No installed browser can take over {}, please install one.",
+                self.name
+            )
+        })?;
+
+        self.rebind(&browser)
+    }
+}
+
+pub fn find_orphaned_apps() -> Vec<OrphanedApp> {
+    let Ok(entries) = fs::read_dir(desktop_filepath("")) else {
+        return Vec::new();
+    };
+
+    let mut apps = Vec::new();
+
+    for entry in entries.flatten() {
+        let filename = entry.file_name().to_string_lossy().to_string();
+
+        let Some(codename) = filename
+            .strip_prefix("webapp-")
+            .and_then(|name| name.strip_suffix(".desktop"))
+        else {
+            continue;
+        };
+
+        match OrphanedApp::read(&entry.path(), codename) {
+            Ok(Some(app)) => apps.push(app),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Cannot read {}: {}", filename, e),
+        }
+    }
+
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+    apps
+}
@@ -1,16 +1,27 @@
+use std::path::PathBuf;
+
 use cosmic::widget::{text, warning};
 use cosmic::{
     app::{message::app, Message as CosmicMessage},
     iced::{id, Length},
+    iced_widget::Scrollable,
     style, theme,
     widget::{self, dropdown, toggler, Container},
     Command, Element,
 };
 
 use crate::{
-    common::{get_supported_browsers, icon_cache_get, url_valid, Browser, BrowserType},
-    fl,
+    chromium::ChromiumSettings,
+    common::{
+        get_supported_browsers, icon_cache_get, parse_keywords, url_valid, Browser, BrowserType,
+        Support, WebAppAction, WebAppTranslation,
+    },
+    extensions, fl,
     pages::{self, iconpicker::IconType},
+    prefs::PrefOverride,
+    profiles::{profiles_for, sharing_supported, SharedProfile},
+    userchrome::{site_theme_color, Color, Layout, Tint},
+    version::outdated_options,
     warning::{WarnAction, WarnMessages},
 };
 
@@ -31,6 +42,26 @@ pub struct AppCreator {
     pub app_navbar: bool,
     pub app_incognito: bool,
     pub app_isolated: bool,
+    pub app_description: String,
+    pub app_keywords: String,
+    pub app_translations: Vec<WebAppTranslation>,
+    pub app_actions: Vec<WebAppAction>,
+    /// Firefox prefs as typed, name and value.
+    pub app_prefs: Vec<(String, String)>,
+    pub app_extensions: Vec<PathBuf>,
+    pub app_layouts: Vec<String>,
+    pub app_layout: Layout,
+    pub app_tints: Vec<String>,
+    pub app_tint: Tint,
+    /// Zoom as typed, in percent.
+    pub app_zoom: String,
+    pub app_dark_mode: bool,
+    pub app_translate: bool,
+    pub app_download_dir: String,
+    /// Profile choices, a profile of the app's own first.
+    pub app_profiles: Vec<String>,
+    pub app_shared_profile: Option<String>,
+    pub app_new_profile: String,
     pub selected_icon: Option<pages::iconpicker::Icon>,
     pub app_browsers: Vec<Browser>,
     pub selected_browser: Option<usize>,
@@ -45,6 +76,23 @@ pub enum Message {
     Arguments(String),
     Browser(usize),
     Category(usize),
+    Description(String),
+    Keywords(String),
+    TranslationLocale(usize, String),
+    TranslationName(usize, String),
+    TranslationComment(usize, String),
+    ActionName(usize, String),
+    ActionUrl(usize, String),
+    PrefName(usize, String),
+    PrefValue(usize, String),
+    AddExtensions(Vec<PathBuf>),
+    Layout(usize),
+    Tint(usize),
+    SiteColor(Option<Color>),
+    Zoom(String),
+    DownloadDir(String),
+    SharedProfile(usize),
+    NewProfile(String),
 
     Clicked(Buttons),
 }
@@ -54,6 +102,16 @@ pub enum Buttons {
     Navbar(bool),
     IsolatedProfile(bool),
     Incognito(bool),
+    AddTranslation,
+    RemoveTranslation(usize),
+    AddAction,
+    RemoveAction(usize),
+    AddPref,
+    RemovePref(usize),
+    RemoveExtension(usize),
+    DarkMode(bool),
+    Translate(bool),
+    CreateProfile,
 }
 
 impl AppCreator {
@@ -73,7 +131,7 @@ impl AppCreator {
             fl!("sound-and-video"),
         ];
 
-        AppCreator {
+        let mut creator = AppCreator {
             app_codename: None,
             app_title_id: id::Id::new("app-title"),
             app_title: String::new(),
@@ -89,12 +147,54 @@ impl AppCreator {
             app_navbar: false,
             app_incognito: false,
             app_isolated: true,
+            app_description: String::new(),
+            app_keywords: String::new(),
+            app_translations: Vec::new(),
+            app_actions: Vec::new(),
+            app_prefs: Vec::new(),
+            app_extensions: Vec::new(),
+            app_layouts: vec![
+                fl!("layout-minimal"),
+                fl!("layout-compact"),
+                fl!("layout-tabs"),
+            ],
+            app_layout: Layout::default(),
+            app_tints: vec![fl!("tint-none"), fl!("tint-cosmic"), fl!("tint-site")],
+            app_tint: Tint::default(),
+            app_zoom: String::from("100"),
+            app_dark_mode: false,
+            app_translate: false,
+            app_download_dir: String::new(),
+            app_profiles: Vec::new(),
+            app_shared_profile: None,
+            app_new_profile: String::new(),
             selected_icon: None,
             app_browsers: browsers,
             selected_browser: Some(0),
             dialog_open: false,
             edit_mode: false,
+        };
+
+        creator.refresh_profiles();
+        creator
+    }
+
+    /// Lists the shared profiles of the chosen browser, leaving one that
+    /// does not belong to it.
+    pub fn refresh_profiles(&mut self) {
+        let names: Vec<String> = profiles_for(&self.app_browser)
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect();
+
+        if let Some(name) = &self.app_shared_profile {
+            if !names.contains(name) {
+                self.app_shared_profile = None;
+            }
         }
+
+        self.app_profiles = vec![fl!("isolated-profile-always")];
+        self.app_profiles.extend(names);
     }
 
     pub fn update(&mut self, message: Message) -> Command<CosmicMessage<pages::Message>> {
@@ -146,7 +246,18 @@ impl AppCreator {
                 self.selected_browser = Some(idx);
                 self.app_browser = browser.clone();
 
-                match browser._type {
+                // packages of the other engine cannot be loaded
+                let family = browser._type.family();
+                self.app_extensions
+                    .retain(|path| extensions::check(family, path).is_ok());
+
+                let capabilities = browser.capabilities();
+                self.app_navbar = capabilities.navbar.resolve(self.app_navbar);
+                self.app_isolated = capabilities.isolated_profile.resolve(self.app_isolated);
+                self.app_incognito = capabilities.private_window.resolve(self.app_incognito);
+                self.refresh_profiles();
+
+                let command = match browser._type {
                     BrowserType::NoBrowser => Command::perform(async {}, |_| {
                         app(pages::Message::Warning((
                             WarnAction::Add,
@@ -159,13 +270,127 @@ impl AppCreator {
                             WarnMessages::AppBrowser,
                         )))
                     }),
-                }
+                };
+
+                Command::batch(vec![command, self.outdated_warning()])
             }
             Message::Category(idx) => {
                 self.app_category.clone_from(&self.app_categories[idx]);
                 self.selected_category = idx;
                 Command::none()
             }
+            Message::Description(description) => {
+                self.app_description = description;
+                Command::none()
+            }
+            Message::Keywords(keywords) => {
+                self.app_keywords = keywords;
+                Command::none()
+            }
+            Message::TranslationLocale(idx, locale) => {
+                if let Some(translation) = self.app_translations.get_mut(idx) {
+                    translation.locale = locale;
+                }
+                Command::none()
+            }
+            Message::TranslationName(idx, name) => {
+                if let Some(translation) = self.app_translations.get_mut(idx) {
+                    translation.name = name;
+                }
+                Command::none()
+            }
+            Message::TranslationComment(idx, comment) => {
+                if let Some(translation) = self.app_translations.get_mut(idx) {
+                    translation.comment = comment;
+                }
+                Command::none()
+            }
+            Message::ActionName(idx, name) => {
+                if let Some(action) = self.app_actions.get_mut(idx) {
+                    action.name = name;
+                }
+                Command::none()
+            }
+            Message::ActionUrl(idx, url) => {
+                if let Some(action) = self.app_actions.get_mut(idx) {
+                    action.url = url;
+                }
+                Command::none()
+            }
+            Message::PrefName(idx, name) => {
+                if let Some(pref) = self.app_prefs.get_mut(idx) {
+                    pref.0 = name;
+                }
+                Command::none()
+            }
+            Message::PrefValue(idx, value) => {
+                if let Some(pref) = self.app_prefs.get_mut(idx) {
+                    pref.1 = value;
+                }
+                Command::none()
+            }
+            Message::Layout(idx) => {
+                self.app_layout = Layout::ALL[idx];
+                Command::none()
+            }
+            Message::Tint(idx) => match idx {
+                0 => {
+                    self.app_tint = Tint::None;
+                    Command::none()
+                }
+                1 => {
+                    self.app_tint = Tint::Cosmic;
+                    Command::none()
+                }
+                // the color is looked up once and kept
+                _ => {
+                    let url = self.app_url.clone();
+
+                    Command::perform(async move { site_theme_color(&url).await }, |color| {
+                        app(pages::Message::Creator(Message::SiteColor(color)))
+                    })
+                }
+            },
+            Message::SiteColor(color) => {
+                match color {
+                    Some(color) => self.app_tint = Tint::Color(color),
+                    None => tracing::warn!("{} does not declare a theme color", self.app_url),
+                }
+                Command::none()
+            }
+            Message::Zoom(zoom) => {
+                self.app_zoom = zoom;
+                Command::none()
+            }
+            Message::DownloadDir(dir) => {
+                self.app_download_dir = dir;
+                Command::none()
+            }
+            Message::SharedProfile(idx) => {
+                self.app_shared_profile = match idx {
+                    0 => None,
+                    _ => self.app_profiles.get(idx).cloned(),
+                };
+                Command::none()
+            }
+            Message::NewProfile(name) => {
+                self.app_new_profile = name;
+                Command::none()
+            }
+            Message::AddExtensions(paths) => {
+                let family = self.app_browser._type.family();
+
+                for path in paths {
+                    match extensions::check(family, &path) {
+                        Ok(()) if !self.app_extensions.contains(&path) => {
+                            self.app_extensions.push(path)
+                        }
+                        Ok(()) => {}
+                        Err(e) => tracing::warn!("{}", e),
+                    }
+                }
+                Command::none()
+            }
 
             Message::Clicked(buttons) => match buttons {
                 Buttons::Navbar(selected) => {
@@ -175,18 +400,119 @@ impl AppCreator {
                 }
                 Buttons::IsolatedProfile(selected) => {
                     self.app_isolated = selected;
+                    if !selected {
+                        self.app_shared_profile = None;
+                    }
 
-                    Command::none()
+                    self.outdated_warning()
                 }
                 Buttons::Incognito(selected) => {
                     self.app_incognito = selected;
 
+                    Command::none()
+                }
+                Buttons::AddTranslation => {
+                    self.app_translations.push(WebAppTranslation::default());
+
+                    Command::none()
+                }
+                Buttons::RemoveTranslation(idx) => {
+                    if idx < self.app_translations.len() {
+                        self.app_translations.remove(idx);
+                    }
+
+                    Command::none()
+                }
+                Buttons::AddAction => {
+                    self.app_actions.push(WebAppAction::default());
+
+                    Command::none()
+                }
+                Buttons::RemoveAction(idx) => {
+                    if idx < self.app_actions.len() {
+                        self.app_actions.remove(idx);
+                    }
+
+                    Command::none()
+                }
+                Buttons::AddPref => {
+                    self.app_prefs.push((String::new(), String::new()));
+
+                    Command::none()
+                }
+                Buttons::RemovePref(idx) => {
+                    if idx < self.app_prefs.len() {
+                        self.app_prefs.remove(idx);
+                    }
+
+                    Command::none()
+                }
+                Buttons::DarkMode(selected) => {
+                    self.app_dark_mode = selected;
+
+                    Command::none()
+                }
+                Buttons::Translate(selected) => {
+                    self.app_translate = selected;
+
+                    Command::none()
+                }
+                Buttons::RemoveExtension(idx) => {
+                    if idx < self.app_extensions.len() {
+                        self.app_extensions.remove(idx);
+                    }
+
+                    Command::none()
+                }
+                Buttons::CreateProfile => {
+                    match SharedProfile::create(&self.app_new_profile, &self.app_browser) {
+                        Ok(profile) => {
+                            self.app_shared_profile = Some(profile.name);
+                            self.app_new_profile.clear();
+                            self.refresh_profiles();
+                        }
+                        Err(e) => tracing::error!("Cannot create profile: {}", e),
+                    }
+
                     Command::none()
                 }
             },
         }
     }
 
+    /// Picks up browser versions that were probed after the creator opened.
+    pub fn refresh_browsers(&mut self) -> Command<CosmicMessage<pages::Message>> {
+        let browsers = get_supported_browsers();
+
+        for browser in self
+            .app_browsers
+            .iter_mut()
+            .chain(std::iter::once(&mut self.app_browser))
+        {
+            if let Some(found) = browsers.iter().find(|found| found.name == browser.name) {
+                browser.version.clone_from(&found.version);
+                browser.label.clone_from(&found.label);
+            }
+        }
+
+        self.outdated_warning()
+    }
+
+    fn outdated_warning(&self) -> Command<CosmicMessage<pages::Message>> {
+        let action = if outdated_options(&self.app_browser, self.app_isolated).is_empty() {
+            WarnAction::Remove
+        } else {
+            WarnAction::Add
+        };
+
+        Command::perform(async {}, move |_| {
+            app(pages::Message::Warning((
+                action,
+                WarnMessages::OutdatedBrowser,
+            )))
+        })
+    }
+
     fn icon_picker_icon(&self, icon: Option<pages::iconpicker::Icon>) -> Element<pages::Message> {
         let ico = if let Some(ico) = icon {
             match ico.icon {
@@ -222,6 +548,262 @@ impl AppCreator {
         .into()
     }
 
+    pub fn keywords(&self) -> Vec<String> {
+        parse_keywords(&self.app_keywords)
+    }
+
+    /// A zoom that is not a number keeps the default.
+    pub fn chromium_settings(&self) -> ChromiumSettings {
+        let defaults = ChromiumSettings::default();
+        let download_dir = self.app_download_dir.trim();
+
+        ChromiumSettings {
+            zoom: self
+                .app_zoom
+                .trim()
+                .trim_end_matches('%')
+                .parse()
+                .ok()
+                .filter(|zoom| (25..=500).contains(zoom))
+                .unwrap_or(defaults.zoom),
+            dark_mode: self.app_dark_mode,
+            translate: self.app_translate,
+            download_dir: (!download_dir.is_empty()).then(|| PathBuf::from(download_dir)),
+        }
+    }
+
+    /// Prefs without a name are dropped.
+    pub fn prefs(&self) -> Vec<PrefOverride> {
+        self.app_prefs
+            .iter()
+            .filter_map(|(name, value)| PrefOverride::parse(&format!("{}={}", name, value)))
+            .collect()
+    }
+
+    fn translations_editor(&self) -> Element<pages::Message> {
+        let mut translations = widget::column().spacing(10);
+
+        for (idx, translation) in self.app_translations.iter().enumerate() {
+            let locale = widget::text_input(fl!("language"), &translation.locale)
+                .on_input(move |s| pages::Message::Creator(Message::TranslationLocale(idx, s)))
+                .width(Length::Fixed(80.));
+            let name = widget::text_input(fl!("title"), &translation.name)
+                .on_input(move |s| pages::Message::Creator(Message::TranslationName(idx, s)))
+                .width(Length::FillPortion(1));
+            let comment = widget::text_input(fl!("description"), &translation.comment)
+                .on_input(move |s| pages::Message::Creator(Message::TranslationComment(idx, s)))
+                .width(Length::FillPortion(2));
+            let remove = widget::button(icon_cache_get("edit-delete-symbolic", 16))
+                .on_press(pages::Message::Creator(Message::Clicked(
+                    Buttons::RemoveTranslation(idx),
+                )))
+                .style(style::Button::Icon);
+
+            translations = translations.push(
+                widget::row()
+                    .push(locale)
+                    .push(name)
+                    .push(comment)
+                    .push(remove)
+                    .spacing(10),
+            );
+        }
+
+        let add_translation = widget::button(text(fl!("add-translation")))
+            .on_press(pages::Message::Creator(Message::Clicked(
+                Buttons::AddTranslation,
+            )))
+            .width(Length::Fill);
+
+        translations.push(add_translation).into()
+    }
+
+    fn actions_editor(&self) -> Element<pages::Message> {
+        let mut actions = widget::column().spacing(10);
+
+        for (idx, action) in self.app_actions.iter().enumerate() {
+            let name = widget::text_input(fl!("action-name"), &action.name)
+                .on_input(move |s| pages::Message::Creator(Message::ActionName(idx, s)))
+                .width(Length::FillPortion(1));
+            let url = widget::text_input(fl!("url"), &action.url)
+                .on_input(move |s| pages::Message::Creator(Message::ActionUrl(idx, s)))
+                .width(Length::FillPortion(2));
+            let remove = widget::button(icon_cache_get("edit-delete-symbolic", 16))
+                .on_press(pages::Message::Creator(Message::Clicked(
+                    Buttons::RemoveAction(idx),
+                )))
+                .style(style::Button::Icon);
+
+            actions = actions.push(widget::row().push(name).push(url).push(remove).spacing(10));
+        }
+
+        let add_action = widget::button(text(fl!("add-action")))
+            .on_press(pages::Message::Creator(Message::Clicked(
+                Buttons::AddAction,
+            )))
+            .width(Length::Fill);
+
+        actions.push(add_action).into()
+    }
+
+    fn prefs_editor(&self) -> Element<pages::Message> {
+        let mut prefs = widget::column()
+            .push(text(fl!("firefox-prefs")))
+            .spacing(10);
+
+        for (idx, (name, value)) in self.app_prefs.iter().enumerate() {
+            let name = widget::text_input(fl!("pref-name"), name)
+                .on_input(move |s| pages::Message::Creator(Message::PrefName(idx, s)))
+                .width(Length::FillPortion(2));
+            let value = widget::text_input(fl!("pref-value"), value)
+                .on_input(move |s| pages::Message::Creator(Message::PrefValue(idx, s)))
+                .width(Length::FillPortion(1));
+            let remove = widget::button(icon_cache_get("edit-delete-symbolic", 16))
+                .on_press(pages::Message::Creator(Message::Clicked(
+                    Buttons::RemovePref(idx),
+                )))
+                .style(style::Button::Icon);
+
+            prefs = prefs.push(
+                widget::row()
+                    .push(name)
+                    .push(value)
+                    .push(remove)
+                    .spacing(10),
+            );
+        }
+
+        let add_pref = widget::button(text(fl!("add-pref")))
+            .on_press(pages::Message::Creator(Message::Clicked(Buttons::AddPref)))
+            .width(Length::Fill);
+
+        prefs.push(add_pref).into()
+    }
+
+    fn window_style(&self) -> Element<pages::Message> {
+        let mut row = widget::row().spacing(10);
+
+        // layouts only matter while Firefox hides its own toolbars
+        if !self.app_navbar {
+            let selected = Layout::ALL
+                .iter()
+                .position(|layout| *layout == self.app_layout);
+
+            row = row.push(text(fl!("layout"))).push(
+                dropdown(&self.app_layouts, selected, |idx| {
+                    pages::Message::Creator(Message::Layout(idx))
+                })
+                .width(Length::Fixed(200.)),
+            );
+        }
+
+        let selected = match self.app_tint {
+            Tint::None => 0,
+            Tint::Cosmic => 1,
+            Tint::Color(_) => 2,
+        };
+
+        row = row.push(text(fl!("tint"))).push(
+            dropdown(&self.app_tints, Some(selected), |idx| {
+                pages::Message::Creator(Message::Tint(idx))
+            })
+            .width(Length::Fixed(200.)),
+        );
+
+        if let Tint::Color(color) = self.app_tint {
+            row = row.push(text(color.to_string()));
+        }
+
+        row.into()
+    }
+
+    fn chromium_settings_editor(&self) -> Element<pages::Message> {
+        let zoom = widget::text_input(fl!("zoom"), &self.app_zoom)
+            .on_input(|s| pages::Message::Creator(Message::Zoom(s)))
+            .width(Length::Fixed(80.));
+        let download_dir = widget::text_input(fl!("download-dir"), &self.app_download_dir)
+            .on_input(|s| pages::Message::Creator(Message::DownloadDir(s)))
+            .width(Length::Fill);
+
+        let toggles = widget::row()
+            .push(
+                toggler(fl!("dark-mode"), self.app_dark_mode, |b| {
+                    pages::Message::Creator(Message::Clicked(Buttons::DarkMode(b)))
+                })
+                .width(Length::Fill),
+            )
+            .push(
+                toggler(fl!("translate"), self.app_translate, |b| {
+                    pages::Message::Creator(Message::Clicked(Buttons::Translate(b)))
+                })
+                .width(Length::Fill),
+            )
+            .spacing(10);
+
+        widget::column()
+            .push(
+                widget::row()
+                    .push(text(fl!("zoom")))
+                    .push(zoom)
+                    .push(download_dir)
+                    .spacing(10),
+            )
+            .push(toggles)
+            .spacing(10)
+            .into()
+    }
+
+    fn profile_picker(&self) -> Element<pages::Message> {
+        let selected = match &self.app_shared_profile {
+            Some(name) => self.app_profiles.iter().position(|profile| profile == name),
+            None => Some(0),
+        };
+
+        let profiles = dropdown(&self.app_profiles, selected, |idx| {
+            pages::Message::Creator(Message::SharedProfile(idx))
+        })
+        .width(Length::Fixed(200.));
+        let new_profile = widget::text_input(fl!("new-profile"), &self.app_new_profile)
+            .on_input(|s| pages::Message::Creator(Message::NewProfile(s)))
+            .width(Length::Fill);
+        let create_profile = widget::button(text(fl!("create-profile"))).on_press(
+            pages::Message::Creator(Message::Clicked(Buttons::CreateProfile)),
+        );
+
+        widget::row()
+            .push(text(fl!("shared-profile")))
+            .push(profiles)
+            .push(new_profile)
+            .push(create_profile)
+            .spacing(10)
+            .into()
+    }
+
+    fn extensions_editor(&self) -> Element<pages::Message> {
+        let mut extensions = widget::column().push(text(fl!("extensions"))).spacing(10);
+
+        for (idx, path) in self.app_extensions.iter().enumerate() {
+            let remove = widget::button(icon_cache_get("edit-delete-symbolic", 16))
+                .on_press(pages::Message::Creator(Message::Clicked(
+                    Buttons::RemoveExtension(idx),
+                )))
+                .style(style::Button::Icon);
+
+            extensions = extensions.push(
+                widget::row()
+                    .push(text(path.to_string_lossy().to_string()).width(Length::Fill))
+                    .push(remove)
+                    .spacing(10),
+            );
+        }
+
+        let add_extension = widget::button(text(fl!("add-extension")))
+            .on_press(pages::Message::OpenExtensionDialog)
+            .width(Length::Fill);
+
+        extensions.push(add_extension).into()
+    }
+
     pub fn view(&self, warnings: String) -> Element<pages::Message> {
         let app_title = widget::text_input(fl!("title"), &self.app_title)
             .id(self.app_title_id.clone())
@@ -253,6 +835,14 @@ impl AppCreator {
             .spacing(10)
             .width(Length::Fill);
 
+        let app_description = widget::text_input(fl!("description"), &self.app_description)
+            .on_input(|s| pages::Message::Creator(Message::Description(s)))
+            .width(Length::Fill);
+
+        let app_keywords = widget::text_input(fl!("keywords"), &self.app_keywords)
+            .on_input(|s| pages::Message::Creator(Message::Keywords(s)))
+            .width(Length::Fill);
+
         let app_arguments = widget::text_input(fl!("non-standard-arguments"), &self.app_parameters)
             .on_input(|s| pages::Message::Creator(Message::Arguments(s)))
             .width(Length::Fill);
@@ -264,26 +854,46 @@ impl AppCreator {
         )
         .width(Length::Fixed(200.));
 
-        let navbar_toggle = toggler(fl!("navbar"), self.app_navbar, |b| {
-            pages::Message::Creator(Message::Clicked(Buttons::Navbar(b)))
-        })
-        .width(Length::Fill);
+        let capabilities = self.app_browser.capabilities();
+        let mut browser_specific = widget::row().spacing(10).width(Length::Fill);
 
-        let browser_specific = match self.app_browser._type {
-            BrowserType::Firefox => navbar_toggle,
-            BrowserType::FirefoxFlatpak => navbar_toggle,
+        if capabilities.navbar == Support::Optional {
+            browser_specific = browser_specific.push(
+                toggler(fl!("navbar"), self.app_navbar, |b| {
+                    pages::Message::Creator(Message::Clicked(Buttons::Navbar(b)))
+                })
+                .width(Length::Fill),
+            );
+        }
+
+        match capabilities.isolated_profile {
+            Support::Optional => {
+                browser_specific = browser_specific.push(
+                    toggler(fl!("isolated-profile"), self.app_isolated, |b| {
+                        pages::Message::Creator(Message::Clicked(Buttons::IsolatedProfile(b)))
+                    })
+                    .width(Length::Fill),
+                )
+            }
+            Support::Always => {
+                browser_specific =
+                    browser_specific.push(text(fl!("isolated-profile-always")).width(Length::Fill))
+            }
+            Support::Unsupported => {}
+        }
 
-            _ => toggler(fl!("isolated-profile"), self.app_isolated, |b| {
-                pages::Message::Creator(Message::Clicked(Buttons::IsolatedProfile(b)))
+        let incognito: Element<pages::Message> = match capabilities.private_window {
+            Support::Optional => toggler(fl!("private-mode"), self.app_incognito, |b| {
+                pages::Message::Creator(Message::Clicked(Buttons::Incognito(b)))
             })
-            .width(Length::Fill),
+            .width(Length::Fill)
+            .into(),
+            Support::Always => text(fl!("private-mode-always")).width(Length::Fill).into(),
+            Support::Unsupported => text(fl!("private-mode-unsupported"))
+                .width(Length::Fill)
+                .into(),
         };
 
-        let incognito = toggler(fl!("private-mode"), self.app_incognito, |b| {
-            pages::Message::Creator(Message::Clicked(Buttons::Incognito(b)))
-        })
-        .width(Length::Fill);
-
         let save_btn = if self.edit_mode {
             widget::button(Container::new(text(fl!("edit"))).center_x())
                 .on_press(pages::Message::Clicked(pages::Buttons::DoneEdit((
@@ -322,12 +932,40 @@ impl AppCreator {
         let view_column = widget::column()
             .push(warning(warnings))
             .push(row)
+            .push(app_description)
+            .push(app_keywords)
             .push(app_arguments)
             .push(first_row)
             .push(end_row)
+            .push(self.translations_editor())
+            .push(self.actions_editor())
             .spacing(10)
             .padding(30);
 
-        Container::new(view_column).max_width(1000).into()
+        // a shared profile is still a profile apart from the personal one
+        let view_column = match sharing_supported(&self.app_browser) && self.app_isolated {
+            true => view_column.push(self.profile_picker()),
+            false => view_column,
+        };
+
+        // only Firefox reads user.js, and settings written into a shared
+        // profile would be those of the app saved last
+        let view_column = match self.app_browser._type.family() {
+            _ if self.app_shared_profile.is_some() => {
+                view_column.push(text(fl!("shared-profile-settings")))
+            }
+            "firefox" => view_column
+                .push(self.window_style())
+                .push(self.prefs_editor())
+                .push(self.extensions_editor()),
+            "chromium" => view_column
+                .push(self.chromium_settings_editor())
+                .push(self.extensions_editor()),
+            _ => view_column,
+        };
+
+        Container::new(Scrollable::new(view_column))
+            .max_width(1000)
+            .into()
     }
 }
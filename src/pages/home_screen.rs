@@ -1,12 +1,15 @@
 use crate::{
-    common::{get_webapps, icon_cache_get, WebAppLauncher},
+    common::{get_webapps, icon_cache_get, launcher_backups, WebAppLauncher},
     fl,
+    orphan::find_orphaned_apps,
     pages::{Buttons, Message},
+    profiles::shared_profiles,
+    sandbox::Flatpak,
 };
 
 use cosmic::widget::{column, editable_input, inline_input};
 use cosmic::{
-    iced::Alignment,
+    iced::{Alignment, Length},
     iced_widget::Scrollable,
     style, theme,
     widget::{self, text, Container},
@@ -56,6 +59,11 @@ impl Home {
                 .padding(10)
                 .style(style::Button::Icon);
 
+            let export = widget::button(icon_cache_get("document-save-symbolic", 16))
+                .on_press(Message::Clicked(Buttons::Export(app.clone())))
+                .padding(10)
+                .style(style::Button::Icon);
+
             let delete = widget::button(icon_cache_get("edit-delete-symbolic", 16))
                 .on_press(Message::Clicked(Buttons::Delete(app.clone())))
                 .padding(10)
@@ -65,17 +73,130 @@ impl Home {
                 .spacing(4)
                 .push(used_browser)
                 .push(app_name);
-            let row2 = cosmic::widget::row().spacing(10).push(edit).push(delete);
+            let mut row2 = cosmic::widget::row().spacing(10).push(edit);
+
+            if !launcher_backups(&app.codename).is_empty() {
+                row2 = row2.push(
+                    widget::button(icon_cache_get("edit-undo-symbolic", 16))
+                        .on_press(Message::Clicked(Buttons::Restore(app.codename.clone())))
+                        .padding(10)
+                        .style(style::Button::Icon),
+                );
+            }
+
+            let row2 = row2.push(export).push(delete);
+
+            let mut entry = column().push(cosmic::widget::row().push(row).push(row2));
+
+            // Flatpak browsers cannot open profiles outside their sandbox
+            if app.profile_blocked() {
+                let mut row = cosmic::widget::row()
+                    .spacing(10)
+                    .padding(10)
+                    .align_items(Alignment::Center)
+                    .push(text(fl!("profile-blocked")));
+
+                // overrides can only be written from outside our own sandbox
+                if Flatpak::system().can_override() {
+                    row = row.push(
+                        widget::button(text(fl!("allow-profile-access")))
+                            .on_press(Message::Clicked(Buttons::AllowProfileAccess(app.clone()))),
+                    );
+                }
+
+                let move_profile = widget::button(text(fl!("move-profile-to-sandbox")))
+                    .on_press(Message::Clicked(Buttons::MoveProfileToSandbox(app.clone())));
+
+                entry = entry.push(row.push(move_profile));
+            }
+
+            app_list = app_list.push(Container::new(entry).style(theme::Container::List));
+        }
+
+        // apps whose browser went away would otherwise just disappear
+        let orphans = find_orphaned_apps();
+        let mut rebindable = 0;
 
+        for app in orphans.iter() {
+            let status = text(fl!(
+                "browser-missing",
+                name = app.name.clone(),
+                browser = app.browser_name.clone()
+            ));
+
+            let mut row = cosmic::widget::row()
+                .spacing(10)
+                .padding(10)
+                .align_items(Alignment::Center)
+                .push(status);
+
+            match app.candidates().first() {
+                Some(browser) => {
+                    rebindable += 1;
+                    row = row.push(
+                        widget::button(text(fl!("rebind-to", browser = browser.label.clone())))
+                            .on_press(Message::Clicked(Buttons::Rebind(app.clone()))),
+                    );
+                }
+                None => row = row.push(text(fl!("browser-missing-none"))),
+            }
+
+            app_list = app_list.push(Container::new(row).style(theme::Container::List));
+        }
+
+        if rebindable > 1 {
             app_list = app_list.push(
-                Container::new(cosmic::widget::row().push(row).push(row2))
-                    .style(theme::Container::List),
+                widget::button(text(fl!("rebind-all")))
+                    .on_press(Message::Clicked(Buttons::RebindAll)),
             );
         }
 
+        // a profile is only deleted on its own once no app uses it
+        let profiles = shared_profiles();
+
+        for profile in profiles.iter() {
+            let users: Vec<String> = profile
+                .users(None)
+                .into_iter()
+                .map(|app| app.name)
+                .collect();
+
+            let mut row = cosmic::widget::row()
+                .spacing(10)
+                .padding(10)
+                .align_items(Alignment::Center);
+
+            if users.is_empty() {
+                row = row
+                    .push(
+                        text(fl!(
+                            "profile-unused",
+                            name = profile.name.clone(),
+                            browser = profile.browser.clone()
+                        ))
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button(icon_cache_get("edit-delete-symbolic", 16))
+                            .on_press(Message::Clicked(Buttons::DeleteProfile(profile.clone())))
+                            .padding(10)
+                            .style(style::Button::Icon),
+                    );
+            } else {
+                row = row.push(text(fl!(
+                    "profile-users",
+                    name = profile.name.clone(),
+                    browser = profile.browser.clone(),
+                    apps = users.join(", ")
+                )));
+            }
+
+            app_list = app_list.push(Container::new(row).style(theme::Container::List));
+        }
+
         let mut final_content = column().spacing(20).align_items(Alignment::Center);
 
-        if !webapps.is_empty() {
+        if !webapps.is_empty() || !orphans.is_empty() || !profiles.is_empty() {
             final_content = final_content.push(Scrollable::new(app_list));
         } else {
             final_content = final_content.push(text(fl!("not-installed-header")).size(20));
@@ -0,0 +1,121 @@
+use crate::{
+    common::{get_supported_browsers, icon_cache_get, Browser},
+    fl,
+    foreign::{find_foreign_apps, ForeignApp},
+    pages::{Buttons, Message},
+};
+
+use cosmic::{
+    iced::{Alignment, Length},
+    iced_widget::Scrollable,
+    style, theme,
+    widget::{self, column, dropdown, text, toggler, Container},
+    Element,
+};
+
+#[derive(Debug, Clone)]
+pub struct Importer {
+    pub apps: Vec<ForeignApp>,
+    pub browsers: Vec<Browser>,
+    pub selected_browser: Option<usize>,
+    pub move_profiles: bool,
+}
+
+impl Importer {
+    pub fn new() -> Self {
+        let browsers: Vec<Browser> = get_supported_browsers()
+            .into_iter()
+            .filter(|browser| browser.is_installed())
+            .collect();
+        let selected_browser = (!browsers.is_empty()).then_some(0);
+
+        Self {
+            apps: find_foreign_apps(),
+            browsers,
+            selected_browser,
+            move_profiles: false,
+        }
+    }
+
+    /// Browser picked for apps whose own browser is not installed.
+    pub fn fallback_browser(&self) -> Option<Browser> {
+        self.selected_browser
+            .and_then(|index| self.browsers.get(index))
+            .cloned()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        if self.apps.is_empty() {
+            return Container::new(text(fl!("importer-empty")).size(20))
+                .padding(30)
+                .max_width(1000)
+                .into();
+        }
+
+        let fallback = dropdown(
+            &self.browsers,
+            self.selected_browser,
+            Message::ImporterBrowser,
+        )
+        .width(Length::Fixed(200.));
+
+        let options = widget::row()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push(text(fl!("fallback-browser")))
+            .push(fallback)
+            .push(
+                toggler(
+                    fl!("move-profile"),
+                    self.move_profiles,
+                    Message::ImporterMoveProfiles,
+                )
+                .width(Length::Fill),
+            );
+
+        let mut app_list = column().spacing(12).align_items(Alignment::Center);
+
+        for app in self.apps.iter() {
+            let browser = app
+                .browser
+                .clone()
+                .or_else(|| self.fallback_browser())
+                .map(|browser| browser.name)
+                .unwrap_or_default();
+
+            let details = column()
+                .spacing(4)
+                .push(text(format!("{} ({})", app.name, app.source)))
+                .push(text(format!("{} → {}", app.url, browser)).size(12))
+                .width(Length::Fill);
+
+            let mut adopt = widget::button(icon_cache_get("list-add-symbolic", 16))
+                .padding(10)
+                .style(style::Button::Icon);
+
+            if !app.url.is_empty() && !browser.is_empty() {
+                adopt = adopt.on_press(Message::Clicked(Buttons::Adopt(app.clone())));
+            }
+
+            app_list = app_list.push(
+                Container::new(
+                    widget::row()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(details)
+                        .push(adopt),
+                )
+                .padding(10)
+                .style(theme::Container::List),
+            );
+        }
+
+        let content = column()
+            .spacing(20)
+            .push(text(fl!("importer-header")).size(20))
+            .push(options)
+            .push(Scrollable::new(app_list));
+
+        Container::new(content).padding(30).max_width(1000).into()
+    }
+}
@@ -2,6 +2,7 @@ pub mod creator;
 pub mod home_screen;
 pub mod iconpicker;
 pub mod icons_installator;
+pub mod importer;
 
 use std::path::PathBuf;
 use std::process::ExitStatus;
@@ -23,15 +24,21 @@ use cosmic::{
 
 use crate::{
     add_icon_packs_install_script,
+    bundle::{export_webapp, import_webapp, BUNDLE_EXTENSION},
     common::{
         self, find_icon, find_icons, get_icon_name_from_url, get_supported_browsers,
         icon_cache_get, image_handle, move_icon, my_icons_location, Browser, BrowserType,
         WebAppLauncher,
     },
-    execute_script, fl, icon_pack_installed,
+    execute_script, fl,
+    foreign::ForeignApp,
+    icon_pack_installed,
+    orphan::{find_orphaned_apps, OrphanedApp},
     pages::home_screen::Home,
     pages::iconpicker::IconPicker,
     pages::icons_installator::Installator,
+    pages::importer::Importer,
+    profiles::SharedProfile,
     warning::WarnMessages,
     warning::{WarnAction, Warning},
 };
@@ -41,9 +48,17 @@ pub enum Buttons {
     SearchFavicon,
     Edit(WebAppLauncher),
     Delete(WebAppLauncher),
+    Export(WebAppLauncher),
+    Restore(String),
+    AllowProfileAccess(WebAppLauncher),
+    MoveProfileToSandbox(WebAppLauncher),
+    Rebind(OrphanedApp),
+    RebindAll,
     DoneEdit((Option<String>, Option<String>)),
     DoneCreate,
     AppNameSubmit(WebAppLauncher),
+    Adopt(ForeignApp),
+    DeleteProfile(SharedProfile),
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -55,8 +70,15 @@ pub enum Message {
     OpenIconPicker,
     OpenIconPickerDialog,
     OpenFileResult(Vec<String>),
+    OpenExtensionDialog,
+    ImportWebApp,
+    OpenImporter,
+    ImporterBrowser(usize),
+    ImporterMoveProfiles(bool),
+    Adopted(PathBuf),
     Creator(creator::Message),
     LoadingDone,
+    BrowserVersions(bool),
 
     EditAppName(bool),
     AppNameInput(String),
@@ -85,6 +107,7 @@ pub enum Pages {
     AppCreator,
     IconPicker,
     IconInstallator(Installator),
+    Importer(Importer),
 }
 
 pub struct Window {
@@ -130,7 +153,17 @@ impl Application for Window {
             warning: warn_element,
         };
 
-        let commands = Command::batch(vec![windows.set_title()]);
+        // running browsers to ask for their version can take seconds
+        let versions = Command::perform(
+            async {
+                tokio::task::spawn_blocking(common::probe_browser_versions)
+                    .await
+                    .unwrap_or_default()
+            },
+            |learned| app(Message::BrowserVersions(learned)),
+        );
+
+        let commands = Command::batch(vec![windows.set_title(), versions]);
 
         (windows, commands)
     }
@@ -138,6 +171,8 @@ impl Application for Window {
     fn header_start(&self) -> Vec<Element<Self::Message>> {
         let go_home_icon = icon_cache_get("go-home-symbolic", 16);
         let go_creator = icon_cache_get("document-new-symbolic", 16);
+        let import = icon_cache_get("document-open-symbolic", 16);
+        let importer = icon_cache_get("system-software-install-symbolic", 16);
         let cosmic_theme::Spacing { space_xxs, .. } = self.core().system_theme().cosmic().spacing;
 
         vec![
@@ -151,6 +186,16 @@ impl Application for Window {
                 .padding(space_xxs)
                 .style(style::Button::Icon)
                 .into(),
+            widget::button(import)
+                .on_press(Message::ImportWebApp)
+                .padding(space_xxs)
+                .style(style::Button::Icon)
+                .into(),
+            widget::button(importer)
+                .on_press(Message::OpenImporter)
+                .padding(space_xxs)
+                .style(style::Button::Icon)
+                .into(),
         ]
     }
 
@@ -167,6 +212,7 @@ impl Application for Window {
             }
             Pages::IconPicker => vec![text(fl!("icon-selector")).into()],
             Pages::IconInstallator(_) => vec![text(fl!("icon-installer")).into()],
+            Pages::Importer(_) => vec![text(fl!("importer")).into()],
         }
     }
 
@@ -190,6 +236,10 @@ impl Application for Window {
 
                 Command::none()
             }
+            Message::BrowserVersions(learned) => match learned {
+                true => self.creator_window.refresh_browsers(),
+                false => Command::none(),
+            },
             Message::Creator(message) => {
                 let command = self.creator_window.update(message);
 
@@ -236,6 +286,94 @@ impl Application for Window {
                     |files| cosmic::app::message::app(Message::OpenFileResult(files)),
                 );
             }
+            Message::OpenExtensionDialog => {
+                // Firefox takes packages, Chromium unpacked directories
+                let chromium = self.creator_window.app_browser._type.family() == "chromium";
+
+                Command::perform(
+                    async move {
+                        let request = SelectedFiles::open_file()
+                            .title("Add extensions")
+                            .accept_label("Add")
+                            .modal(true)
+                            .multiple(true);
+                        let request = match chromium {
+                            true => request.directory(true),
+                            false => {
+                                request.filter(FileFilter::new("Firefox Add-on").glob("*.xpi"))
+                            }
+                        };
+                        let result = request.send().await.and_then(|request| request.response());
+
+                        match result {
+                            Ok(files) => files
+                                .uris()
+                                .iter()
+                                .filter_map(|uri| uri.to_file_path().ok())
+                                .collect(),
+                            Err(_) => Vec::new(),
+                        }
+                    },
+                    |paths| app(Message::Creator(creator::Message::AddExtensions(paths))),
+                )
+            }
+            Message::ImportWebApp => Command::perform(
+                async move {
+                    let result = SelectedFiles::open_file()
+                        .title("Import web apps")
+                        .accept_label("Import")
+                        .modal(true)
+                        .multiple(true)
+                        .filter(FileFilter::new("Web App").glob(&format!("*.{}", BUNDLE_EXTENSION)))
+                        .send()
+                        .await
+                        .and_then(|request| request.response());
+
+                    if let Ok(files) = result {
+                        let paths: Vec<PathBuf> = files
+                            .uris()
+                            .iter()
+                            .filter_map(|uri| uri.to_file_path().ok())
+                            .collect();
+
+                        blocking(move || {
+                            for path in paths {
+                                if let Err(e) = import_webapp(&path) {
+                                    tracing::error!("Cannot import {:?}: {}", path, e);
+                                }
+                            }
+                        })
+                        .await;
+                    }
+                },
+                |_| app(Message::OpenHome),
+            ),
+            Message::OpenImporter => {
+                self.current_page = Pages::Importer(Importer::new());
+
+                Command::none()
+            }
+            Message::ImporterBrowser(index) => {
+                if let Pages::Importer(importer) = &mut self.current_page {
+                    importer.selected_browser = Some(index);
+                }
+
+                Command::none()
+            }
+            Message::ImporterMoveProfiles(flag) => {
+                if let Pages::Importer(importer) = &mut self.current_page {
+                    importer.move_profiles = flag;
+                }
+
+                Command::none()
+            }
+            Message::Adopted(path) => {
+                if let Pages::Importer(importer) = &mut self.current_page {
+                    importer.apps.retain(|app| app.path != path);
+                }
+
+                Command::none()
+            }
             Message::OpenFileResult(result) => {
                 for path in result {
                     if let Ok(buf) = PathBuf::from_str(&path) {
@@ -266,7 +404,7 @@ impl Application for Window {
             }
             Message::Clicked(buttons) => match buttons {
                 Buttons::DoneCreate => {
-                    let new_entry = WebAppLauncher::new(
+                    let mut new_entry = WebAppLauncher::new(
                         self.creator_window.app_title.clone(),
                         None,
                         self.creator_window.app_url.clone(),
@@ -279,8 +417,31 @@ impl Application for Window {
                         self.creator_window.app_incognito,
                     );
 
+                    new_entry
+                        .description
+                        .clone_from(&self.creator_window.app_description);
+                    new_entry.keywords = self.creator_window.keywords();
+                    new_entry
+                        .translations
+                        .clone_from(&self.creator_window.app_translations);
+                    new_entry
+                        .actions
+                        .clone_from(&self.creator_window.app_actions);
+                    new_entry.prefs = self.creator_window.prefs();
+                    new_entry
+                        .extensions
+                        .clone_from(&self.creator_window.app_extensions);
+                    new_entry.layout = self.creator_window.app_layout;
+                    new_entry.tint = self.creator_window.app_tint;
+                    new_entry.chromium = self.creator_window.chromium_settings();
+                    new_entry
+                        .shared_profile
+                        .clone_from(&self.creator_window.app_shared_profile);
+
                     if new_entry.is_valid {
-                        self.create_valid_launcher(new_entry).unwrap();
+                        if let Err(e) = self.create_valid_launcher(new_entry, None) {
+                            tracing::error!("Cannot save web app: {}", e);
+                        }
                     } else {
                         self.warning.push_warn(WarnMessages::Duplicate);
                     }
@@ -289,10 +450,9 @@ impl Application for Window {
                 }
                 Buttons::DoneEdit((new_name, old_icon)) => {
                     if let Some(launcher) = self.main_window.launcher.to_owned() {
-                        let _deleted = launcher.delete();
                         let mut edited_entry = WebAppLauncher::new(
                             self.creator_window.app_title.clone(),
-                            Some(launcher.codename),
+                            Some(launcher.codename.clone()),
                             self.creator_window.app_url.clone(),
                             self.creator_window.app_icon.clone(),
                             self.creator_window.app_category.clone(),
@@ -311,8 +471,45 @@ impl Application for Window {
                             edited_entry.icon = old_icon.unwrap();
                         }
 
+                        edited_entry
+                            .description
+                            .clone_from(&self.creator_window.app_description);
+                        edited_entry.keywords = self.creator_window.keywords();
+                        edited_entry
+                            .translations
+                            .clone_from(&self.creator_window.app_translations);
+                        edited_entry
+                            .actions
+                            .clone_from(&self.creator_window.app_actions);
+                        edited_entry.prefs = self.creator_window.prefs();
+                        edited_entry
+                            .extensions
+                            .clone_from(&self.creator_window.app_extensions);
+                        edited_entry.layout = self.creator_window.app_layout;
+                        edited_entry.tint = self.creator_window.app_tint;
+                        edited_entry.chromium = self.creator_window.chromium_settings();
+
+                        // keep keys and groups this app does not manage
+                        edited_entry
+                            .desktop_entry
+                            .clone_from(&launcher.desktop_entry);
+
+                        // a moved profile belongs to the browser it was moved for
+                        if edited_entry.web_browser.name == launcher.web_browser.name {
+                            edited_entry
+                                .profile_location
+                                .clone_from(&launcher.profile_location);
+                        }
+                        edited_entry
+                            .shared_profile
+                            .clone_from(&self.creator_window.app_shared_profile);
+
                         if edited_entry.is_valid {
-                            self.create_valid_launcher(edited_entry).unwrap();
+                            if let Err(e) =
+                                self.create_valid_launcher(edited_entry, Some(&launcher))
+                            {
+                                tracing::error!("Cannot save web app: {}", e);
+                            }
                         } else {
                             self.warning.push_warn(WarnMessages::Duplicate);
                         }
@@ -354,6 +551,31 @@ impl Application for Window {
                     self.creator_window.selected_browser = selected_browser;
                     self.creator_window.app_navbar = launcher.navbar;
                     self.creator_window.app_incognito = launcher.is_incognito;
+                    self.creator_window.app_description = launcher.description;
+                    self.creator_window.app_keywords = launcher.keywords.join(", ");
+                    self.creator_window.app_translations = launcher.translations;
+                    self.creator_window.app_actions = launcher.actions;
+                    self.creator_window.app_prefs = launcher
+                        .prefs
+                        .iter()
+                        .map(|pref| {
+                            let value = pref.value.as_ref().map(ToString::to_string);
+                            (pref.name.clone(), value.unwrap_or_default())
+                        })
+                        .collect();
+                    self.creator_window.app_extensions = launcher.extensions;
+                    self.creator_window.app_layout = launcher.layout;
+                    self.creator_window.app_tint = launcher.tint;
+                    self.creator_window.app_zoom = launcher.chromium.zoom.to_string();
+                    self.creator_window.app_dark_mode = launcher.chromium.dark_mode;
+                    self.creator_window.app_translate = launcher.chromium.translate;
+                    self.creator_window.app_download_dir = launcher
+                        .chromium
+                        .download_dir
+                        .map(|dir| dir.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    self.creator_window.app_shared_profile = launcher.shared_profile;
+                    self.creator_window.refresh_profiles();
                     self.creator_window.edit_mode = true;
 
                     Command::perform(image_handle(launcher.icon), |result| {
@@ -365,6 +587,107 @@ impl Application for Window {
 
                     Command::none()
                 }
+                Buttons::AllowProfileAccess(launcher) => Command::perform(
+                    blocking(move || {
+                        if let Err(e) = launcher.allow_profile_access() {
+                            tracing::error!("Cannot allow access for {}: {}", launcher.codename, e);
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::MoveProfileToSandbox(mut launcher) => Command::perform(
+                    blocking(move || {
+                        if let Err(e) = launcher.move_profile_to_sandbox() {
+                            tracing::error!("Cannot move profile of {}: {}", launcher.codename, e);
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::Rebind(orphan) => Command::perform(
+                    blocking(move || {
+                        if let Err(e) = orphan.rebind_to_best() {
+                            tracing::error!("Cannot rebind {}: {}", orphan.codename, e);
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::RebindAll => Command::perform(
+                    blocking(|| {
+                        for orphan in find_orphaned_apps() {
+                            if let Err(e) = orphan.rebind_to_best() {
+                                tracing::error!("Cannot rebind {}: {}", orphan.codename, e);
+                            }
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::DeleteProfile(profile) => Command::perform(
+                    blocking(move || {
+                        if let Err(e) = profile.delete() {
+                            tracing::error!("Cannot delete profile {}: {}", profile.name, e);
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::Restore(codename) => Command::perform(
+                    blocking(move || {
+                        if let Err(e) = WebAppLauncher::restore(&codename) {
+                            tracing::error!("Cannot restore {}: {}", codename, e);
+                        }
+                    }),
+                    |_| message::none(),
+                ),
+                Buttons::Export(launcher) => Command::perform(
+                    async move {
+                        let file_name = format!("{}.{}", launcher.codename, BUNDLE_EXTENSION);
+                        let result = SelectedFiles::save_file()
+                            .title("Export web app")
+                            .accept_label("Export")
+                            .modal(true)
+                            .current_name(file_name.as_str())
+                            .send()
+                            .await
+                            .and_then(|request| request.response());
+
+                        if let Ok(files) = result {
+                            if let Some(Ok(path)) =
+                                files.uris().first().map(|uri| uri.to_file_path())
+                            {
+                                blocking(move || {
+                                    if let Err(e) = export_webapp(&launcher, &path) {
+                                        tracing::error!("Cannot export {:?}: {}", path, e);
+                                    }
+                                })
+                                .await;
+                            }
+                        }
+                    },
+                    |_| message::none(),
+                ),
+                Buttons::Adopt(foreign_app) => {
+                    let Pages::Importer(importer) = &self.current_page else {
+                        return Command::none();
+                    };
+                    let browser = foreign_app
+                        .browser
+                        .clone()
+                        .or_else(|| importer.fallback_browser());
+                    let move_profiles = importer.move_profiles;
+
+                    Command::perform(
+                        blocking(move || match foreign_app.adopt(browser, move_profiles) {
+                            Ok(_) => Some(foreign_app.path),
+                            Err(e) => {
+                                tracing::error!("Cannot import {:?}: {}", foreign_app.path, e);
+                                None
+                            }
+                        }),
+                        |adopted| match adopted.flatten() {
+                            Some(path) => app(Message::Adopted(path)),
+                            None => message::none(),
+                        },
+                    )
+                }
                 Buttons::SearchFavicon => {
                     if common::url_valid(&self.creator_window.app_url) {
                         self.icon_selector.icons.clear();
@@ -535,6 +858,7 @@ impl Application for Window {
             Pages::AppCreator => self.creator_window.view(self.warning.messages()),
             Pages::IconPicker => self.icon_selector.view(),
             Pages::IconInstallator(installator) => installator.view(),
+            Pages::Importer(importer) => importer.view(),
         };
 
         Container::new(view)
@@ -550,13 +874,26 @@ impl Window {
     fn set_title(&mut self) -> Command<CosmicMessage<Message>> {
         self.set_window_title(fl!("app"))
     }
-    fn create_valid_launcher(&mut self, entry: WebAppLauncher) -> anyhow::Result<()> {
+    fn create_valid_launcher(
+        &mut self,
+        mut entry: WebAppLauncher,
+        previous: Option<&WebAppLauncher>,
+    ) -> anyhow::Result<()> {
+        // the creator hides them, but they may be left from before
+        if entry.shared_profile.is_some() {
+            entry.drop_profile_settings();
+        }
+
         move_icon(
             self.creator_window.app_icon.clone(),
             self.creator_window.app_title.clone(),
         );
 
-        entry.create().unwrap();
+        // on failure the previous launcher stays in place untouched
+        match previous {
+            Some(previous) => entry.replace(previous)?,
+            None => entry.create()?,
+        }
         self.creator_window.edit_mode = false;
         self.current_page = Pages::MainWindow;
 
@@ -580,3 +917,13 @@ impl Window {
         }
     }
 }
+
+// profile moves, flatpak calls and archive IO can take a while, keep them off
+// the UI thread
+async fn blocking<T, F>(task: F) -> Option<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(task).await.ok()
+}
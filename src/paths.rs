@@ -0,0 +1,156 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::Lazy;
+
+#[cfg(test)]
+static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+const APP_DIR: &str = "quick-webapps";
+
+type BaseDir = fn() -> PathBuf;
+
+static IN_FLATPAK: Lazy<bool> =
+    Lazy::new(|| env::var_os("FLATPAK_ID").is_some() || Path::new("/.flatpak-info").exists());
+
+/// Whether we run inside the Flatpak sandbox ourselves.
+pub fn in_flatpak() -> bool {
+    *IN_FLATPAK
+}
+
+pub fn home_dir() -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+}
+
+fn base_dir(var: &str, fallback: &str) -> PathBuf {
+    absolute_or(env::var_os(var), fallback)
+}
+
+// Inside the sandbox XDG_* point into ~/.var/app, which is where our own
+// data belongs, but launchers and icons have to land where the host desktop
+// looks for them. Flatpak passes the host values as HOST_XDG_*.
+fn host_base_dir(var: &str, fallback: &str) -> PathBuf {
+    if !*IN_FLATPAK {
+        return base_dir(var, fallback);
+    }
+
+    absolute_or(env::var_os(format!("HOST_{}", var)), fallback)
+}
+
+// the spec says relative paths are invalid and should be ignored
+fn absolute_or(value: Option<OsString>, fallback: &str) -> PathBuf {
+    value
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| home_dir().join(fallback))
+}
+
+pub fn data_home() -> PathBuf {
+    base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn config_home() -> PathBuf {
+    base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn cache_home() -> PathBuf {
+    base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// `XDG_DATA_HOME` of the host, the same as [`data_home`] outside Flatpak.
+pub fn host_data_home() -> PathBuf {
+    host_base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `XDG_CONFIG_HOME` of the host, the same as [`config_home`] outside Flatpak.
+pub fn host_config_home() -> PathBuf {
+    host_base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `XDG_CACHE_HOME` of the host, the same as [`cache_home`] outside Flatpak.
+pub fn host_cache_home() -> PathBuf {
+    host_base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+pub fn data_dirs() -> Vec<PathBuf> {
+    let dirs: Vec<PathBuf> = env::var("XDG_DATA_DIRS")
+        .unwrap_or_default()
+        .split(':')
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .collect();
+
+    if dirs.is_empty() {
+        return vec![
+            PathBuf::from("/usr/local/share"),
+            PathBuf::from("/usr/share"),
+        ];
+    }
+
+    dirs
+}
+
+pub fn app_data_dir() -> PathBuf {
+    data_home().join(APP_DIR)
+}
+
+pub fn app_cache_dir() -> PathBuf {
+    cache_home().join(APP_DIR)
+}
+
+// browsers.toml is written by hand on the host, the manifest grants it
+// read-only
+pub fn app_config_dir() -> PathBuf {
+    host_config_home().join(APP_DIR)
+}
+
+/// Where the app kept its data before it followed `XDG_DATA_HOME`.
+pub fn legacy_app_data_dir() -> PathBuf {
+    home_dir().join(".local/share").join(APP_DIR)
+}
+
+/// Resolves a path from the browser tables. Absolute paths are kept,
+/// `.local/share`, `.config` and `.cache` follow their XDG variables and
+/// anything else is relative to the home directory.
+pub fn resolve(path: &str) -> PathBuf {
+    let path = Path::new(path);
+
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let bases: [(&str, BaseDir); 3] = [
+        (".local/share", data_home),
+        (".config", config_home),
+        (".cache", cache_home),
+    ];
+
+    for (prefix, base) in bases {
+        if let Ok(rest) = path.strip_prefix(prefix) {
+            return base().join(rest);
+        }
+    }
+
+    home_dir().join(path)
+}
+
+/// Runs `f` with `XDG_DATA_HOME` pointing to `dir`. All test threads share
+/// the environment, so tests doing this take turns.
+#[cfg(test)]
+pub fn with_data_home<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = env::var_os("XDG_DATA_HOME");
+
+    env::set_var("XDG_DATA_HOME", dir);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+
+    match previous {
+        Some(value) => env::set_var("XDG_DATA_HOME", value),
+        None => env::remove_var("XDG_DATA_HOME"),
+    }
+
+    result.unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::version::{pref_supported, Version};
+
+// prefs every Firefox web app starts with, each can be overridden per app
+const DEFAULT_USER_JS: &str = include_str!("../data/runtime/firefox/profile/user.js");
+
+/// Value of a Firefox pref, as it is written in `user.js`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PrefValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl PrefValue {
+    /// Reads `true`, `2` or `"strict"`. Unquoted text that is neither a
+    /// boolean nor a number is taken as a string, so it can be typed as is.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+
+        if text.is_empty() {
+            return None;
+        }
+
+        let value = match text {
+            "true" => PrefValue::Bool(true),
+            "false" => PrefValue::Bool(false),
+            _ => match text.parse() {
+                Ok(number) => PrefValue::Int(number),
+                Err(_) if text.starts_with('"') => {
+                    PrefValue::String(serde_json::from_str(text).ok()?)
+                }
+                Err(_) => PrefValue::String(text.to_string()),
+            },
+        };
+
+        Some(value)
+    }
+}
+
+impl fmt::Display for PrefValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefValue::Bool(value) => write!(f, "{}", value),
+            PrefValue::Int(value) => write!(f, "{}", value),
+            PrefValue::String(value) => write!(
+                f,
+                "{}",
+                serde_json::to_string(value).map_err(|_| fmt::Error)?
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pref {
+    pub name: String,
+    pub value: PrefValue,
+}
+
+impl Pref {
+    /// Reads a `user_pref("name", value);` line.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let args = line
+            .trim()
+            .strip_prefix("user_pref(")?
+            .trim_end()
+            .strip_suffix(';')?
+            .trim_end()
+            .strip_suffix(')')?;
+        let (name, value) = args.split_once(',')?;
+
+        Some(Self {
+            name: serde_json::from_str(name.trim()).ok()?,
+            value: PrefValue::parse(value)?,
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!(
+            "user_pref({}, {});",
+            PrefValue::String(self.name.clone()),
+            self.value
+        )
+    }
+}
+
+/// A pref an app sets differently from the bundled `user.js`. Without a
+/// value the pref is left to the browser.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefOverride {
+    pub name: String,
+    pub value: Option<PrefValue>,
+}
+
+impl PrefOverride {
+    /// Reads `name=value`, where an empty value leaves the pref to the browser.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (name, value) = text.split_once('=').unwrap_or((text, ""));
+        let name = name.trim();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            value: PrefValue::parse(value),
+        })
+    }
+}
+
+impl fmt::Display for PrefOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={}", self.name, value),
+            None => write!(f, "{}=", self.name),
+        }
+    }
+}
+
+pub fn default_prefs() -> Vec<Pref> {
+    DEFAULT_USER_JS
+        .lines()
+        .filter_map(Pref::parse_line)
+        .collect()
+}
+
+/// Prefs the manager owns for an app, and the ones among them to write.
+/// Defaults the installed release does not read are owned but not written.
+pub fn app_prefs(
+    overrides: &[PrefOverride],
+    version: Option<&Version>,
+) -> (HashSet<String>, Vec<Pref>) {
+    let mut owned = HashSet::new();
+    let mut prefs = Vec::new();
+
+    for pref in default_prefs() {
+        owned.insert(pref.name.clone());
+
+        if pref_supported(&pref.name, version) {
+            prefs.push(pref);
+        }
+    }
+
+    for pref_override in overrides {
+        owned.insert(pref_override.name.clone());
+        prefs.retain(|pref| pref.name != pref_override.name);
+
+        if let Some(value) = &pref_override.value {
+            prefs.push(Pref {
+                name: pref_override.name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    (owned, prefs)
+}
+
+/// Rewrites the owned prefs of an existing `user.js` in place and appends
+/// the missing ones. Lines the user added by hand are kept as they are.
+pub fn merge_user_js(existing: &str, owned: &HashSet<String>, prefs: &[Pref]) -> String {
+    let mut written = HashSet::new();
+    let mut lines = Vec::new();
+
+    for line in existing.lines() {
+        let Some(name) = Pref::parse_line(line)
+            .map(|pref| pref.name)
+            .filter(|name| owned.contains(name))
+        else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        if let Some(pref) = prefs.iter().find(|pref| pref.name == name) {
+            if written.insert(name) {
+                lines.push(pref.to_line());
+            }
+        }
+    }
+
+    for pref in prefs.iter().filter(|pref| !written.contains(&pref.name)) {
+        lines.push(pref.to_line());
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(PrefValue::parse("true"), Some(PrefValue::Bool(true)));
+        assert_eq!(PrefValue::parse(" 2 "), Some(PrefValue::Int(2)));
+        assert_eq!(
+            PrefValue::parse("\"strict\""),
+            Some(PrefValue::String(String::from("strict")))
+        );
+        assert_eq!(
+            PrefValue::parse("standard"),
+            Some(PrefValue::String(String::from("standard")))
+        );
+        assert_eq!(PrefValue::parse(""), None);
+    }
+
+    #[test]
+    fn reads_bundled_user_js() {
+        let prefs = default_prefs();
+
+        assert!(prefs.contains(&Pref {
+            name: String::from("browser.link.open_newwindow"),
+            value: PrefValue::Int(2),
+        }));
+        assert_eq!(
+            prefs.len(),
+            DEFAULT_USER_JS
+                .lines()
+                .filter(|line| line.starts_with("user_pref"))
+                .count()
+        );
+    }
+
+    #[test]
+    fn overrides_replace_and_unset_defaults() {
+        let overrides = [
+            PrefOverride::parse("browser.link.open_newwindow=3").unwrap(),
+            PrefOverride::parse("browser.cache.disk.enable=").unwrap(),
+        ];
+        let (owned, prefs) = app_prefs(&overrides, None);
+
+        assert!(owned.contains("browser.cache.disk.enable"));
+        assert!(!prefs
+            .iter()
+            .any(|pref| pref.name == "browser.cache.disk.enable"));
+        assert!(prefs.contains(&Pref {
+            name: String::from("browser.link.open_newwindow"),
+            value: PrefValue::Int(3),
+        }));
+    }
+
+    #[test]
+    fn merge_keeps_hand_written_prefs() {
+        let existing = "// my settings
+user_pref(\"browser.link.open_newwindow\", 2);
+user_pref(\"media.autoplay.default\", 5);
+user_pref(\"browser.cache.disk.enable\", false);";
+
+        let overrides = [
+            PrefOverride::parse("browser.link.open_newwindow=3").unwrap(),
+            PrefOverride::parse("browser.cache.disk.enable=").unwrap(),
+        ];
+        let (owned, prefs) = app_prefs(&overrides, None);
+        let merged = merge_user_js(existing, &owned, &prefs);
+        let lines: Vec<&str> = merged.lines().collect();
+
+        assert_eq!(lines[0], "// my settings");
+        assert_eq!(lines[1], "user_pref(\"browser.link.open_newwindow\", 3);");
+        assert_eq!(lines[2], "user_pref(\"media.autoplay.default\", 5);");
+        assert!(!merged.contains("browser.cache.disk.enable"));
+        assert!(merged.contains("user_pref(\"browser.contentblocking.category\", \"strict\");"));
+        assert_eq!(merge_user_js(&merged, &owned, &prefs), merged);
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codename::slugify,
+    common::{desktop_filepath, write_atomic, Browser, DesktopEntry, DESKTOP_ENTRY_GROUP},
+    foreign::move_profile_dir,
+    paths,
+};
+
+const PROFILES_FILE: &str = "profiles.json";
+
+// below the browser's profile root, where codenames cannot collide as they
+// never start with a dot
+const SHARED_DIR: &str = ".shared";
+
+/// A browser profile that several web apps use together, e.g. to share one
+/// login without touching the personal browser profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedProfile {
+    pub name: String,
+    /// Browser name, profiles of one browser cannot be opened by another.
+    pub browser: String,
+    pub path: PathBuf,
+}
+
+fn profiles_path() -> PathBuf {
+    paths::app_data_dir().join(PROFILES_FILE)
+}
+
+pub fn shared_profiles() -> Vec<SharedProfile> {
+    read(&profiles_path())
+}
+
+fn read(file: &Path) -> Vec<SharedProfile> {
+    fs::read_to_string(file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(file: &Path, profiles: &[SharedProfile]) -> Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    write_atomic(file, serde_json::to_string_pretty(profiles)?.as_bytes())
+}
+
+/// A launcher naming a shared profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileUser {
+    pub path: PathBuf,
+    pub codename: String,
+    pub name: String,
+}
+
+/// Points profiles kept below `from` to the same place below `to`, once
+/// their directories were moved there.
+pub fn rebase_profiles(from: &Path, to: &Path) -> Result<()> {
+    let mut profiles = shared_profiles();
+    let mut changed = false;
+
+    for profile in &mut profiles {
+        if let Ok(rest) = profile.path.strip_prefix(from) {
+            profile.path = to.join(rest);
+            changed = true;
+        }
+    }
+
+    match changed {
+        true => save(&profiles_path(), &profiles),
+        false => Ok(()),
+    }
+}
+
+/// Profiles an app using `browser` can pick.
+pub fn profiles_for(browser: &Browser) -> Vec<SharedProfile> {
+    shared_profiles()
+        .into_iter()
+        .filter(|profile| profile.browser == browser.name)
+        .collect()
+}
+
+/// Whether apps of this browser can share a profile. GNOME Web makes every
+/// profile an application of its own and Falkon only runs one instance per
+/// profile.
+pub fn sharing_supported(browser: &Browser) -> bool {
+    matches!(browser._type.family(), "firefox" | "chromium")
+}
+
+impl SharedProfile {
+    pub fn find(name: &str) -> Option<Self> {
+        shared_profiles()
+            .into_iter()
+            .find(|profile| profile.name == name)
+    }
+
+    pub fn create(name: &str, browser: &Browser) -> Result<Self> {
+        Self::create_in(&profiles_path(), name, browser)
+    }
+
+    fn create_in(file: &Path, name: &str, browser: &Browser) -> Result<Self> {
+        let name = name.trim();
+        let slug = slugify(name);
+
+        if slug.is_empty() {
+            return Err(anyhow!("A profile name needs letters or digits."));
+        }
+
+        if !sharing_supported(browser) {
+            return Err(anyhow!("{} cannot share profiles.", browser.name));
+        }
+
+        let mut profiles = read(file);
+
+        if profiles.iter().any(|profile| profile.name == name) {
+            return Err(anyhow!("A profile named {} already exists.", name));
+        }
+
+        let profile = Self {
+            name: name.to_string(),
+            browser: browser.name.clone(),
+            path: browser.profile_root().join(SHARED_DIR).join(slug),
+        };
+
+        fs::create_dir_all(&profile.path)?;
+        profiles.push(profile.clone());
+        save(file, &profiles)?;
+
+        tracing::info!("Created profile {} in {}", name, profile.path.display());
+
+        Ok(profile)
+    }
+
+    /// Web apps using this profile, `except` one of them. Launchers are read
+    /// as plain files, so apps whose browser is gone count as well.
+    pub fn users(&self, except: Option<&Path>) -> Vec<ProfileUser> {
+        let Ok(entries) = fs::read_dir(desktop_filepath("")) else {
+            return Vec::new();
+        };
+
+        let mut users = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let filename = entry.file_name().to_string_lossy().to_string();
+
+            let Some(codename) = filename
+                .strip_prefix("webapp-")
+                .and_then(|name| name.strip_suffix(".desktop"))
+            else {
+                continue;
+            };
+
+            if Some(path.as_path()) == except {
+                continue;
+            }
+
+            let desktop_entry = match DesktopEntry::read(&path) {
+                Ok(desktop_entry) => desktop_entry,
+                Err(e) => {
+                    tracing::warn!("Cannot read {}: {}", filename, e);
+                    continue;
+                }
+            };
+
+            let Some(group) = desktop_entry.group(DESKTOP_ENTRY_GROUP) else {
+                continue;
+            };
+
+            if group.get("X-WebApp-SharedProfile").as_deref() == Some(self.name.as_str()) {
+                users.push(ProfileUser {
+                    codename: codename.to_string(),
+                    name: group.get("Name").unwrap_or_default(),
+                    path,
+                });
+            }
+        }
+
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+
+        users
+    }
+
+    /// Whether apps of `browser` may use this profile.
+    pub fn check(&self, browser: &Browser) -> Result<()> {
+        match self.browser == browser.name {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "Profile {} belongs to {}, it cannot be used with {}.",
+                self.name,
+                self.browser,
+                browser.name
+            )),
+        }
+    }
+
+    /// Moves the profile directory, e.g. into a Flatpak sandbox, for every
+    /// app using it.
+    pub fn relocate(&self, target: &Path) -> Result<Self> {
+        self.relocate_in(&profiles_path(), target)
+    }
+
+    fn relocate_in(&self, file: &Path, target: &Path) -> Result<Self> {
+        if self.path.exists() {
+            move_profile_dir(&self.path, target)?;
+        }
+
+        let mut profiles = read(file);
+        let mut moved = self.clone();
+        moved.path = target.to_path_buf();
+
+        for profile in profiles.iter_mut().filter(|p| p.name == self.name) {
+            profile.path.clone_from(&moved.path);
+        }
+        save(file, &profiles)?;
+
+        Ok(moved)
+    }
+
+    /// Removes the profile and its data. Refused while apps still use it.
+    pub fn delete(&self) -> Result<()> {
+        let users = self.users(None);
+
+        if !users.is_empty() {
+            let names: Vec<&str> = users.iter().map(|app| app.name.as_str()).collect();
+
+            return Err(anyhow!(
+                "Profile {} is still used by {}.",
+                self.name,
+                names.join(", ")
+            ));
+        }
+
+        self.remove()
+    }
+
+    // also used by `WebAppLauncher::delete` once its last app is gone
+    pub fn remove(&self) -> Result<()> {
+        self.remove_in(&profiles_path())
+    }
+
+    fn remove_in(&self, file: &Path) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_dir_all(&self.path)?;
+        }
+
+        let mut profiles = read(file);
+        profiles.retain(|profile| profile.name != self.name);
+        save(file, &profiles)?;
+
+        tracing::info!("Removed profile {}", self.name);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::BrowserType;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quick-webapps-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn browser(_type: BrowserType, name: &str, root: &Path) -> Browser {
+        Browser::new(_type, name, "browser", "", &root.to_string_lossy())
+    }
+
+    #[test]
+    fn creates_profiles_once() {
+        let dir = temp_dir("profiles-create");
+        let file = dir.join(PROFILES_FILE);
+        let firefox = browser(BrowserType::Firefox, "Firefox", &dir.join("firefox"));
+
+        let profile = SharedProfile::create_in(&file, " Google Workspace ", &firefox).unwrap();
+
+        assert_eq!(profile.name, "Google Workspace");
+        assert_eq!(profile.browser, "Firefox");
+        assert_eq!(profile.path, dir.join("firefox/.shared/google-workspace"));
+        assert!(profile.path.is_dir());
+        assert_eq!(read(&file), vec![profile]);
+
+        assert!(SharedProfile::create_in(&file, "Google Workspace", &firefox).is_err());
+        assert!(SharedProfile::create_in(&file, "!!!", &firefox).is_err());
+        assert_eq!(read(&file).len(), 1);
+    }
+
+    #[test]
+    fn only_some_engines_share_profiles() {
+        let dir = temp_dir("profiles-engines");
+        let file = dir.join(PROFILES_FILE);
+        let falkon = browser(BrowserType::Falkon, "Falkon", &dir);
+        let chromium = browser(BrowserType::Chromium, "Chromium", &dir);
+
+        assert!(SharedProfile::create_in(&file, "Work", &falkon).is_err());
+
+        let profile = SharedProfile::create_in(&file, "Work", &chromium).unwrap();
+        assert!(profile.check(&chromium).is_ok());
+        assert!(profile
+            .check(&browser(BrowserType::Chromium, "Brave", &dir))
+            .is_err());
+    }
+
+    #[test]
+    fn relocates_and_removes_profiles() {
+        let dir = temp_dir("profiles-relocate");
+        let file = dir.join(PROFILES_FILE);
+        let firefox = browser(BrowserType::Firefox, "Firefox", &dir.join("firefox"));
+
+        let profile = SharedProfile::create_in(&file, "Work", &firefox).unwrap();
+        let other = SharedProfile::create_in(&file, "Home", &firefox).unwrap();
+        fs::write(profile.path.join("prefs.js"), "").unwrap();
+
+        let target = dir.join("sandbox/work");
+        let moved = profile.relocate_in(&file, &target).unwrap();
+
+        assert_eq!(moved.path, target);
+        assert!(target.join("prefs.js").exists());
+        assert!(!profile.path.exists());
+        assert_eq!(read(&file), vec![moved.clone(), other.clone()]);
+
+        moved.remove_in(&file).unwrap();
+
+        assert!(!target.exists());
+        assert_eq!(read(&file), vec![other]);
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+
+use crate::{
+    common::{Browser, DesktopEntry},
+    paths,
+    supported_browsers::flatpak_installations,
+};
+
+// the home screen asks on every redraw
+const PERMISSIONS_TTL: Duration = Duration::from_secs(10);
+
+static PERMISSIONS: Lazy<Mutex<HashMap<String, (Instant, Permissions)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Filesystem {
+    path: PathBuf,
+    writable: bool,
+    denied: bool,
+}
+
+/// Filesystem access of a Flatpak application, overrides included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Permissions {
+    filesystems: Vec<Filesystem>,
+}
+
+// Only the locations a profile could sensibly live in are understood,
+// anything else is treated as not granted.
+fn filesystem_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with('/') {
+        return Some(PathBuf::from(name));
+    }
+
+    let (base, rest) = name.split_once('/').unwrap_or((name, ""));
+
+    let base = match base {
+        "host" => PathBuf::from("/"),
+        "home" => paths::home_dir(),
+        "~" => paths::home_dir(),
+        "xdg-data" => paths::host_data_home(),
+        "xdg-config" => paths::host_config_home(),
+        "xdg-cache" => paths::host_cache_home(),
+        "xdg-desktop" => dirs::desktop_dir()?,
+        "xdg-documents" => dirs::document_dir()?,
+        "xdg-download" => dirs::download_dir()?,
+        "xdg-music" => dirs::audio_dir()?,
+        "xdg-pictures" => dirs::picture_dir()?,
+        "xdg-videos" => dirs::video_dir()?,
+        _ => return None,
+    };
+
+    Some(base.join(rest))
+}
+
+impl Permissions {
+    /// Reads the `[Context]` group of application metadata or an override.
+    pub fn parse(content: &str) -> Self {
+        let entry = DesktopEntry::parse(content);
+
+        let filesystems = entry
+            .group("Context")
+            .map(|group| group.get_list("filesystems"))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|filesystem| {
+                let (name, mode) = match filesystem.rsplit_once(':') {
+                    Some((name, mode)) => (name, mode),
+                    None => (filesystem.as_str(), "rw"),
+                };
+                let (name, denied) = match name.strip_prefix('!') {
+                    Some(name) => (name, true),
+                    None => (name, false),
+                };
+
+                Some(Filesystem {
+                    path: filesystem_path(name)?,
+                    writable: mode != "ro",
+                    denied,
+                })
+            })
+            .collect();
+
+        Self { filesystems }
+    }
+
+    /// Permissions of an installed application, from its metadata and the
+    /// overrides of every installation, much like `flatpak info
+    /// --show-permissions` prints them. A deny in an override wins over any
+    /// grant, which at worst reports a profile as blocked that is not.
+    pub fn read(id: &str, installations: &[PathBuf]) -> Option<Self> {
+        let metadata = installations.iter().find_map(|root| {
+            fs::read_to_string(root.join("app").join(id).join("current/active/metadata")).ok()
+        })?;
+
+        let mut permissions = Self::parse(&metadata);
+
+        for root in installations {
+            for name in ["global", id] {
+                if let Ok(content) = fs::read_to_string(root.join("overrides").join(name)) {
+                    permissions
+                        .filesystems
+                        .extend(Self::parse(&content).filesystems);
+                }
+            }
+        }
+
+        Some(permissions)
+    }
+
+    /// Whether application `id` may write to `path`. Its own data
+    /// directory under `~/.var/app` is always reachable.
+    pub fn can_write(&self, id: &str, path: &Path) -> bool {
+        if path.starts_with(sandbox_dir(id)) {
+            return true;
+        }
+
+        let covering = || {
+            self.filesystems
+                .iter()
+                .filter(|filesystem| path.starts_with(&filesystem.path))
+        };
+
+        !covering().any(|filesystem| filesystem.denied)
+            && covering().any(|filesystem| filesystem.writable)
+    }
+}
+
+/// Directory that Flatpak gives every application for its own data.
+pub fn sandbox_dir(id: &str) -> PathBuf {
+    paths::home_dir().join(".var/app").join(id)
+}
+
+/// Where profiles of a Flatpak browser can always be written.
+pub fn sandbox_profile_root(id: &str) -> PathBuf {
+    sandbox_dir(id).join("data/profiles")
+}
+
+/// Application id of a Flatpak browser, from its export or from a
+/// `flatpak run` command line.
+pub fn flatpak_id(browser: &Browser) -> Option<String> {
+    if !browser._type.is_flatpak() {
+        return None;
+    }
+
+    if browser.exec.contains("/exports/bin/") {
+        return Path::new(&browser.exec)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_string);
+    }
+
+    browser
+        .exec
+        .split_whitespace()
+        .skip_while(|arg| *arg != "run")
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .map(str::to_string)
+}
+
+/// Installed Flatpak applications. Permissions are read from the
+/// installations, the `flatpak` command is only needed to change them and
+/// cannot be reached from inside our own sandbox.
+#[derive(Debug, Clone)]
+pub struct Flatpak {
+    program: Option<PathBuf>,
+    installations: Vec<PathBuf>,
+}
+
+impl Flatpak {
+    pub fn system() -> Self {
+        let program = (!paths::in_flatpak()).then(|| PathBuf::from("flatpak"));
+
+        Self::new(program, flatpak_installations())
+    }
+
+    pub fn new(program: Option<PathBuf>, installations: Vec<PathBuf>) -> Self {
+        Self {
+            program,
+            installations,
+        }
+    }
+
+    /// Whether permissions can be changed, see `allow`.
+    pub fn can_override(&self) -> bool {
+        self.program.is_some()
+    }
+
+    pub fn permissions(&self, id: &str) -> Result<Permissions> {
+        Permissions::read(id, &self.installations)
+            .ok_or_else(|| anyhow!("{} is not installed.", id))
+    }
+
+    /// Adds a per-user override giving `id` write access to `path`.
+    pub fn allow(&self, id: &str, path: &Path) -> Result<()> {
+        let filesystem = format!("--filesystem={}", path.display());
+
+        let Some(program) = &self.program else {
+            return Err(anyhow!(
+                "Permissions cannot be changed from inside the sandbox, run: flatpak override --user {} {}",
+                filesystem,
+                id
+            ));
+        };
+
+        let output = Command::new(program)
+            .args(["override", "--user", &filesystem, id])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "flatpak override failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        PERMISSIONS.lock().unwrap().remove(id);
+
+        tracing::info!("Allowed {} to use {}", id, path.display());
+
+        Ok(())
+    }
+
+    fn cached_permissions(&self, id: &str) -> Result<Permissions> {
+        let mut cache = PERMISSIONS.lock().unwrap();
+
+        if let Some((read_at, permissions)) = cache.get(id) {
+            if read_at.elapsed() < PERMISSIONS_TTL {
+                return Ok(permissions.clone());
+            }
+        }
+
+        let permissions = self.permissions(id)?;
+        cache.insert(id.to_string(), (Instant::now(), permissions.clone()));
+
+        Ok(permissions)
+    }
+
+    /// Whether the Flatpak browser cannot write to a profile directory.
+    /// Native browsers, and browsers whose permissions cannot be read, are
+    /// never reported.
+    pub fn profile_blocked(&self, browser: &Browser, profile: &Path) -> bool {
+        let Some(id) = flatpak_id(browser) else {
+            return false;
+        };
+
+        match self.cached_permissions(&id) {
+            Ok(permissions) => !permissions.can_write(&id, profile),
+            Err(e) => {
+                tracing::debug!("Cannot read permissions of {}: {}", id, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+
+    const PERMISSIONS: &str = "[Context]
+shared=network;ipc;
+sockets=x11;wayland;pulseaudio;
+filesystems=xdg-download;~/Sites:ro;/srv/profiles;home;!~/Private;
+
+[Session Bus Policy]
+org.freedesktop.Notifications=talk
+";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quick-webapps-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    // a flatpak stand-in that logs its arguments
+    fn stub(name: &str) -> (PathBuf, PathBuf) {
+        let dir = temp_dir(name);
+        let log = dir.join("calls");
+        let program = dir.join("flatpak");
+        let script = format!("#!/bin/sh\necho \"$@\" >> '{}'\n", log.display());
+
+        fs::write(&program, script).unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+
+        (program, log)
+    }
+
+    #[test]
+    fn parses_filesystems() {
+        let permissions = Permissions::parse(PERMISSIONS);
+        let home = paths::home_dir();
+
+        assert!(permissions.can_write("org.example.Browser", &home.join("profiles")));
+        assert!(permissions.can_write("org.example.Browser", Path::new("/srv/profiles/a")));
+        assert!(!permissions.can_write("org.example.Browser", &home.join("Private/a")));
+        assert!(!permissions.can_write("org.example.Browser", Path::new("/opt/profiles")));
+    }
+
+    #[test]
+    fn own_data_dir_is_always_writable() {
+        let permissions = Permissions::parse("[Context]\nfilesystems=!home;\n");
+        let id = "org.example.Browser";
+
+        assert!(permissions.can_write(id, &sandbox_profile_root(id).join("mail")));
+        assert!(!permissions.can_write(id, &paths::home_dir().join("profiles")));
+    }
+
+    #[test]
+    fn read_only_access_is_not_enough() {
+        let permissions = Permissions::parse("[Context]\nfilesystems=/srv/profiles:ro;\n");
+
+        assert!(!permissions.can_write("org.example.Browser", Path::new("/srv/profiles/a")));
+    }
+
+    #[test]
+    fn reads_permissions_from_installations() {
+        let id = "org.example.Browser";
+        let system = temp_dir("system");
+        let user = temp_dir("user");
+
+        let app = system.join("app").join(id).join("current/active");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("metadata"), PERMISSIONS).unwrap();
+        fs::create_dir_all(user.join("overrides")).unwrap();
+        fs::write(
+            user.join("overrides").join(id),
+            "[Context]\nfilesystems=/opt/profiles;!/srv/profiles;\n",
+        )
+        .unwrap();
+
+        let flatpak = Flatpak::new(None, vec![system, user]);
+        let permissions = flatpak.permissions(id).unwrap();
+
+        assert!(permissions.can_write(id, &paths::home_dir().join("profiles")));
+        assert!(permissions.can_write(id, Path::new("/opt/profiles/a")));
+        assert!(!permissions.can_write(id, Path::new("/srv/profiles/a")));
+        assert!(flatpak.permissions("org.example.Missing").is_err());
+    }
+
+    #[test]
+    fn applies_user_override() {
+        let (program, log) = stub("override");
+        let flatpak = Flatpak::new(Some(program), Vec::new());
+
+        flatpak
+            .allow("org.example.Browser", Path::new("/srv/profiles"))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(log).unwrap(),
+            "override --user --filesystem=/srv/profiles org.example.Browser\n"
+        );
+    }
+
+    #[test]
+    fn reports_failures() {
+        let flatpak = Flatpak::new(Some(PathBuf::from("/bin/false")), Vec::new());
+
+        assert!(flatpak
+            .allow("org.example.Browser", Path::new("/srv/profiles"))
+            .is_err());
+    }
+
+    #[test]
+    fn cannot_override_from_the_sandbox() {
+        let flatpak = Flatpak::new(None, Vec::new());
+
+        assert!(!flatpak.can_override());
+        assert!(flatpak
+            .allow("org.example.Browser", Path::new("/srv/profiles"))
+            .is_err());
+    }
+}
@@ -1,472 +1,684 @@
-use crate::common::{Browser, BrowserType};
+use std::{
+    collections::HashSet,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
-#[allow(dead_code)]
-pub fn native_browsers() -> Vec<Browser> {
+use serde::Deserialize;
+use walkdir::WalkDir;
+
+use crate::{
+    common::{Browser, BrowserType, DesktopEntry, DESKTOP_ENTRY_GROUP},
+    foreign::program_args,
+    gecko::GeckoApp,
+    paths,
+};
+
+// Display names end up in X-WebApp-Browser of every launcher, so browsers we
+// already knew keep the names they always had.
+const KNOWN_EXECUTABLES: &[(&str, &str, BrowserType)] = &[
+    ("firefox", "Firefox", BrowserType::Firefox),
+    (
+        "firefox-developer-edition",
+        "Firefox Developer Edition",
+        BrowserType::Firefox,
+    ),
+    ("firefox-nightly", "Firefox Nightly", BrowserType::Firefox),
+    ("firefox-esr", "Firefox ESR", BrowserType::Firefox),
+    ("brave-browser", "Brave Browser", BrowserType::Chromium),
+    ("brave-bin", "Brave (bin)", BrowserType::Chromium),
+    ("google-chrome-stable", "Chrome", BrowserType::Chromium),
+    ("google-chrome", "Chrome", BrowserType::Chromium),
+    ("google-chrome-beta", "Chrome Beta", BrowserType::Chromium),
+    ("chromium", "Chromium", BrowserType::Chromium),
+    (
+        "chromium-browser",
+        "Chromium Browser",
+        BrowserType::Chromium,
+    ),
+    ("chromium-bin", "Chromium (bin)", BrowserType::Chromium),
+    ("cromite", "Cromite", BrowserType::Chromium),
+    ("thorium-browser", "Thorium", BrowserType::Chromium),
+    ("librewolf", "Librewolf", BrowserType::Firefox),
+    ("waterfox", "Waterfox", BrowserType::Firefox),
+    (
+        "waterfox-current",
+        "Waterfox (current)",
+        BrowserType::Firefox,
+    ),
+    (
+        "waterfox-classic",
+        "Waterfox (classic)",
+        BrowserType::Firefox,
+    ),
+    (
+        "waterfox-g3",
+        "Waterfox 3rd Generation",
+        BrowserType::Firefox,
+    ),
+    (
+        "waterfox-g4",
+        "Waterfox 4rd Generation",
+        BrowserType::Firefox,
+    ),
+    ("floorp", "Floorp", BrowserType::Firefox),
+    ("vivaldi-stable", "Vivaldi", BrowserType::Chromium),
+    ("vivaldi", "Vivaldi", BrowserType::Chromium),
+    (
+        "vivaldi-snapshot",
+        "Vivaldi Snapshot",
+        BrowserType::Chromium,
+    ),
+    (
+        "microsoft-edge-stable",
+        "Microsoft Edge",
+        BrowserType::Chromium,
+    ),
+    ("microsoft-edge", "Microsoft Edge", BrowserType::Chromium),
+    (
+        "microsoft-edge-beta",
+        "Microsoft Edge Beta",
+        BrowserType::Chromium,
+    ),
+    (
+        "microsoft-edge-dev",
+        "Microsoft Edge Dev",
+        BrowserType::Chromium,
+    ),
+    (
+        "flashpeak-slimjet",
+        "FlashPeak Slimjet",
+        BrowserType::Chromium,
+    ),
+    ("yandex-browser", "Yandex", BrowserType::Chromium),
+    ("naver-whale-stable", "Naver Whale", BrowserType::Chromium),
+    ("brave", "Brave", BrowserType::Chromium),
+    ("falkon", "Falkon", BrowserType::Falkon),
+    ("epiphany", "GNOME Web", BrowserType::Epiphany),
+    ("epiphany-browser", "GNOME Web", BrowserType::Epiphany),
+];
+
+const KNOWN_FLATPAKS: &[(&str, &str, BrowserType)] = &[
+    (
+        "org.mozilla.firefox",
+        "Firefox",
+        BrowserType::FirefoxFlatpak,
+    ),
+    ("com.google.Chrome", "Chrome", BrowserType::ChromiumFlatpak),
+    (
+        "io.gitlab.librewolf-community",
+        "Librewolf",
+        BrowserType::FirefoxFlatpak,
+    ),
+    (
+        "net.waterfox.waterfox",
+        "Waterfox",
+        BrowserType::FirefoxFlatpak,
+    ),
+    (
+        "com.vivaldi.Vivaldi",
+        "Vivaldi",
+        BrowserType::ChromiumFlatpak,
+    ),
+    (
+        "io.github.ungoogled_software.ungoogled_chromium",
+        "Ungoogled Chromium",
+        BrowserType::ChromiumFlatpak,
+    ),
+    (
+        "org.chromium.Chromium",
+        "Chromium",
+        BrowserType::ChromiumFlatpak,
+    ),
+    (
+        "com.microsoft.Edge",
+        "Microsoft Edge",
+        BrowserType::ChromiumFlatpak,
+    ),
+    ("com.brave.Browser", "Brave", BrowserType::ChromiumFlatpak),
+    ("org.kde.falkon", "Falkon", BrowserType::FalkonFlatpak),
+    ("ru.yandex.Browser", "Yandex", BrowserType::ChromiumFlatpak),
+    ("one.ablaze.floorp", "Floorp", BrowserType::FirefoxFlatpak),
+    (
+        "org.gnome.Epiphany",
+        "GNOME Web",
+        BrowserType::EpiphanyFlatpak,
+    ),
+];
+
+// snap command names, which are <snap> or <snap>.<app>
+const KNOWN_SNAPS: &[(&str, &str, BrowserType)] = &[
+    ("firefox", "Firefox", BrowserType::FirefoxSnap),
+    ("chromium", "Chromium", BrowserType::ChromiumSnap),
+    ("brave", "Brave", BrowserType::ChromiumSnap),
+    (
+        "vivaldi.vivaldi-stable",
+        "Vivaldi",
+        BrowserType::ChromiumSnap,
+    ),
+    ("opera", "Opera", BrowserType::ChromiumSnap),
+];
+
+// /var/lib/snapd/snap is used where /snap cannot be created, e.g. Fedora
+const SNAP_BIN_DIRS: &[&str] = &["/snap/bin", "/var/lib/snapd/snap/bin"];
+const SNAP_DESKTOP_DIR: &str = "/var/lib/snapd/desktop/applications";
+
+// Browsers whose flags differ from the defaults of their engine, keyed by a
+// prefix of the executable, snap command or Flatpak id: (prefix, exec
+// template, private window flag). See `expand_exec_template` for templates.
+const FLAG_OVERRIDES: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("microsoft-edge", None, Some("--inprivate")),
+    ("com.microsoft.Edge", None, Some("--inprivate")),
+    ("opera", None, Some("--private")),
+];
+
+// MIME types a desktop file has to handle to be taken for a web browser
+const BROWSER_MIME_TYPES: &[&str] = &["x-scheme-handler/http", "x-scheme-handler/https"];
+
+/// Engine family guessed from an executable, application id or display name.
+pub fn family_of(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+
+    if has(&[
+        "firefox",
+        "librewolf",
+        "waterfox",
+        "floorp",
+        "icecat",
+        "mullvad",
+    ]) {
+        "firefox"
+    } else if has(&["falkon"]) {
+        "falkon"
+    } else if has(&["epiphany", "gnome web"]) {
+        "epiphany"
+    } else if has(&[
+        "chrom", "brave", "edge", "vivaldi", "opera", "yandex", "thorium", "whale", "slimjet",
+        "cromite",
+    ]) {
+        "chromium"
+    } else {
+        ""
+    }
+}
+
+fn browser_type(family: &str, flatpak: bool) -> Option<BrowserType> {
+    match (family, flatpak) {
+        ("firefox", false) => Some(BrowserType::Firefox),
+        ("firefox", true) => Some(BrowserType::FirefoxFlatpak),
+        ("chromium", false) => Some(BrowserType::Chromium),
+        ("chromium", true) => Some(BrowserType::ChromiumFlatpak),
+        ("falkon", false) => Some(BrowserType::Falkon),
+        ("falkon", true) => Some(BrowserType::FalkonFlatpak),
+        ("epiphany", false) => Some(BrowserType::Epiphany),
+        ("epiphany", true) => Some(BrowserType::EpiphanyFlatpak),
+        _ => None,
+    }
+}
+
+fn snap_browser_type(family: &str) -> Option<BrowserType> {
+    match family {
+        "firefox" => Some(BrowserType::FirefoxSnap),
+        "chromium" => Some(BrowserType::ChromiumSnap),
+        _ => None,
+    }
+}
+
+// Firefox forks read profiles of each other but upgrade them to their own
+// schema, so every fork gets a root of its own
+fn native_profile_root(_type: &BrowserType, exec: &Path) -> String {
+    let key = match _type.family() {
+        "firefox" => GeckoApp::find(exec)
+            .map(|app| app.profile_key())
+            .unwrap_or_else(|| String::from("firefox")),
+        family => family.to_string(),
+    };
+
+    format!(".local/share/quick-webapps/{}", key)
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// `PATH` first, then places that are often missing from it, like
+/// home-manager and NixOS profiles when started from a desktop session.
+fn search_dirs() -> Vec<PathBuf> {
+    let home = paths::home_dir();
+    let user = env::var("USER").unwrap_or_default();
+
+    let mut dirs: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+
+    dirs.extend([
+        PathBuf::from("/usr/local/bin"),
+        PathBuf::from("/usr/bin"),
+        home.join(".local/bin"),
+        home.join("bin"),
+        home.join(".nix-profile/bin"),
+        PathBuf::from(format!("/etc/profiles/per-user/{}/bin", user)),
+        PathBuf::from("/run/current-system/sw/bin"),
+    ]);
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| dir.is_absolute() && seen.insert(dir.clone()));
+
+    dirs
+}
+
+// Ubuntu keeps /usr/bin/firefox and /usr/bin/chromium-browser as scripts
+// that start the snap, these are left to snap_browsers
+fn is_snap(path: &Path) -> bool {
+    if SNAP_BIN_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return true;
+    }
+
+    let target = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+
+    if target.starts_with("/snap") || target.ends_with("bin/snap") {
+        return true;
+    }
+
+    fs::metadata(&target).is_ok_and(|meta| meta.len() < 4096)
+        && fs::read(&target).is_ok_and(|content| {
+            content.starts_with(b"#!") && String::from_utf8_lossy(&content).contains("/snap/bin/")
+        })
+}
+
+fn which(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+
+    if path.is_absolute() {
+        return is_executable(path).then(|| path.to_path_buf());
+    }
+
+    search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn with_overrides(browser: Browser, key: &str) -> Browser {
+    match FLAG_OVERRIDES
+        .iter()
+        .find(|(prefix, _, _)| key.starts_with(prefix))
+    {
+        Some((_, exec_template, private_flag)) => browser.with_flags(*exec_template, *private_flag),
+        None => browser,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn native_browser(name: &str, _type: BrowserType, exec: &Path) -> Browser {
+    let key = file_name(exec);
+    let profile_root = native_profile_root(&_type, exec);
+    let exec = exec.to_string_lossy();
+
+    with_overrides(Browser::new(_type, name, &exec, &exec, &profile_root), &key)
+}
+
+/// Known browsers in `PATH` and the usual install locations, and vendor
+/// builds unpacked under `/opt`.
+pub fn path_browsers() -> Vec<Browser> {
+    let mut browsers = Vec::new();
+
+    for (executable, name, _type) in KNOWN_EXECUTABLES {
+        if let Some(exec) = which(executable).filter(|exec| !is_snap(exec)) {
+            browsers.push(native_browser(name, _type.clone(), &exec));
+        }
+    }
+
+    for entry in WalkDir::new("/opt")
+        .max_depth(3)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file() && is_executable(entry.path()))
+    {
+        let file_name = entry.file_name().to_string_lossy();
+
+        if let Some((_, name, _type)) = KNOWN_EXECUTABLES
+            .iter()
+            .find(|(executable, _, _)| *executable == file_name)
+        {
+            browsers.push(native_browser(name, _type.clone(), entry.path()));
+            continue;
+        }
+
+        // unknown Firefox forks are told apart by the files they ship
+        if let Some(app) = entry
+            .path()
+            .parent()
+            .and_then(GeckoApp::read)
+            .filter(|app| app.is_main_binary(entry.path()))
+        {
+            browsers.push(native_browser(
+                &app.name,
+                BrowserType::Firefox,
+                entry.path(),
+            ));
+        }
+    }
+
+    browsers
+}
+
+fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![paths::host_data_home().join("applications")];
+    dirs.extend(
+        paths::data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications")),
+    );
+
+    dirs
+}
+
+/// Installed desktop files that handle http(s) links, which also finds
+/// AppImages and browsers installed to unusual places.
+pub fn desktop_file_browsers() -> Vec<Browser> {
+    let mut browsers = Vec::new();
+
+    for path in application_dirs()
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+    {
+        let Ok(desktop_entry) = DesktopEntry::read(&path) else {
+            continue;
+        };
+        let Some(entry) = desktop_entry.group(DESKTOP_ENTRY_GROUP) else {
+            continue;
+        };
+
+        let handles_web = entry
+            .get_list("MimeType")
+            .iter()
+            .any(|mime| BROWSER_MIME_TYPES.contains(&mime.as_str()))
+            || entry
+                .get_list("Categories")
+                .iter()
+                .any(|c| c == "WebBrowser");
+
+        if !handles_web || entry.get_bool("Hidden").unwrap_or(false) {
+            continue;
+        }
+
+        let args = program_args(&entry.get("Exec").unwrap_or_default());
+
+        // Flatpak launchers are picked up from the exports instead
+        let Some(program) = args.first().filter(|program| !program.ends_with("flatpak")) else {
+            continue;
+        };
+        let Some(exec) = which(program).filter(|exec| !is_snap(exec)) else {
+            continue;
+        };
+
+        let file_name = file_name(&exec);
+
+        match KNOWN_EXECUTABLES
+            .iter()
+            .find(|(executable, _, _)| *executable == file_name)
+        {
+            Some((_, name, _type)) => browsers.push(native_browser(name, _type.clone(), &exec)),
+            None => {
+                let name = entry.get("Name").unwrap_or(file_name.clone());
+
+                let family = match GeckoApp::find(&exec) {
+                    Some(_) => "firefox",
+                    None => family_of(&format!("{} {}", file_name, name)),
+                };
+
+                if let Some(_type) = browser_type(family, false) {
+                    browsers.push(native_browser(&name, _type, &exec));
+                }
+            }
+        }
+    }
+
+    browsers
+}
+
+fn snap_browser(name: &str, _type: BrowserType, exec: &Path) -> Browser {
+    let exec = exec.to_string_lossy();
+    let command = exec.rsplit('/').next().unwrap_or_default();
+    let snap = command.split('.').next().unwrap_or_default();
+
+    // confined browsers cannot write hidden directories in home, but the
+    // common directory of the snap survives refreshes
+    let profile_root = format!("snap/{}/common/quick-webapps", snap);
+
+    with_overrides(
+        Browser::new(_type, name, &exec, &exec, &profile_root),
+        command,
+    )
+}
+
+/// Browsers installed as snaps, like Firefox and Chromium on Ubuntu.
+pub fn snap_browsers() -> Vec<Browser> {
+    let mut browsers = Vec::new();
+
+    for bin_dir in SNAP_BIN_DIRS.iter().map(Path::new) {
+        for (command, name, _type) in KNOWN_SNAPS {
+            let exec = bin_dir.join(command);
+
+            if exec.exists() {
+                browsers.push(snap_browser(name, _type.clone(), &exec));
+            }
+        }
+    }
+
+    let Ok(entries) = fs::read_dir(SNAP_DESKTOP_DIR) else {
+        return browsers;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(entry) = DesktopEntry::read(&path)
+            .ok()
+            .and_then(|entry| entry.group(DESKTOP_ENTRY_GROUP).cloned())
+        else {
+            continue;
+        };
+
+        let handles_web = entry
+            .get_list("MimeType")
+            .iter()
+            .any(|mime| BROWSER_MIME_TYPES.contains(&mime.as_str()));
+
+        let args = program_args(&entry.get("Exec").unwrap_or_default());
+
+        let Some(exec) = args
+            .first()
+            .map(PathBuf::from)
+            .filter(|exec| SNAP_BIN_DIRS.iter().any(|dir| exec.starts_with(dir)))
+        else {
+            continue;
+        };
+
+        let command = file_name(&exec);
+
+        if !handles_web || KNOWN_SNAPS.iter().any(|(known, _, _)| *known == command) {
+            continue;
+        }
+
+        let name = entry.get("Name").unwrap_or(command.clone());
+
+        if let Some(_type) = snap_browser_type(family_of(&format!("{} {}", command, name))) {
+            browsers.push(snap_browser(&name, _type, &exec));
+        }
+    }
+
+    browsers
+}
+
+pub fn flatpak_installations() -> Vec<PathBuf> {
     vec![
-        Browser::new(
-            BrowserType::Firefox,
-            "Firefox",
-            "firefox",
-            "/usr/bin/firefox",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Firefox Developer Edition",
-            "firefox-developer-edition",
-            "/usr/bin/firefox-developer-edition",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Firefox Nightly",
-            "firefox-nightly",
-            "/usr/bin/firefox-nightly",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Firefox ESR",
-            "firefox-esr",
-            "/usr/bin/firefox-esr",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Brave Browser",
-            "brave-browser",
-            "/usr/bin/brave-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Brave (bin)",
-            "brave-bin",
-            "/usr/bin/brave-bin",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chrome",
-            "google-chrome-stable",
-            "/usr/bin/google-chrome-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chrome Beta",
-            "google-chrome-beta",
-            "/usr/bin/google-chrome-beta",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chromium",
-            "chromium",
-            "/usr/bin/chromium",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chromium Browser",
-            "chromium-browser",
-            "/usr/bin/chromium-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chromium (bin)",
-            "chromium-bin",
-            "/usr/bin/chromium-bin-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Cromite",
-            "cromite",
-            "/usr/bin/cromite",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Thorium",
-            "thorium-browser",
-            "/usr/bin/thorium-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Librewolf",
-            "librewolf",
-            "/usr/bin/librewolf",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Waterfox",
-            "waterfox",
-            "/usr/bin/waterfox",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Waterfox (current)",
-            "waterfox-current",
-            "/usr/bin/waterfox-current",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Waterfox (classic)",
-            "waterfox-classic",
-            "/usr/bin/waterfox-classic",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Waterfox 3rd Generation",
-            "waterfox-g3",
-            "/usr/bin/waterfox-g3",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Waterfox 4rd Generation",
-            "waterfox-g4",
-            "/usr/bin/waterfox-g4",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Vivaldi",
-            "vivaldi-stable",
-            "/usr/bin/vivaldi-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Vivaldi Snapshot",
-            "vivaldi-snapshot",
-            "/usr/bin/vivaldi-snapshot",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge",
-            "microsoft-edge-stable",
-            "/usr/bin/microsoft-edge-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge Beta",
-            "microsoft-edge-beta",
-            "/usr/bin/microsoft-edge-beta",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge Dev",
-            "microsoft-edge-dev",
-            "/usr/bin/microsoft-edge-dev",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "FlashPeak Slimjet",
-            "flashpeak-slimjet",
-            "/usr/bin/flashpeak-slimjet",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Yandex",
-            "yandex-browser",
-            "/usr/bin/yandex-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Naver Whale",
-            "naver-whale-stable",
-            "/usr/bin/naver-whale-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Brave",
-            "brave",
-            "/usr/bin/brave",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Falkon,
-            "Falkon",
-            "falkon",
-            "/usr/bin/falkon",
-            ".local/share/quick-webapps/falkon",
-        ),
+        PathBuf::from("/var/lib/flatpak"),
+        paths::host_data_home().join("flatpak"),
     ]
 }
 
+/// Every exported Flatpak application that is a browser we can drive.
 pub fn flatpak_browsers() -> Vec<Browser> {
-    vec![
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Firefox",
-            "/var/lib/flatpak/exports/bin/org.mozilla.firefox",
-            "/var/lib/flatpak/exports/bin/org.mozilla.firefox",
-            ".var/app/org.mozilla.firefox/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Firefox",
-            ".local/share/flatpak/exports/bin/org.mozilla.firefox",
-            ".local/share/flatpak/exports/bin/org.mozilla.firefox",
-            ".var/app/org.mozilla.firefox/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Chrome",
-            "/var/lib/flatpak/exports/bin/com.google.Chrome",
-            "/var/lib/flatpak/exports/bin/com.google.Chrome",
-            ".var/app/com.google.Chrome/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Chrome",
-            ".local/share/flatpak/exports/bin/com.google.Chrome",
-            ".local/share/flatpak/exports/bin/com.google.Chrome",
-            ".var/app/com.google.Chrome/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Librewolf",
-            "/var/lib/flatpak/exports/bin/io.gitlab.librewolf-community",
-            "/var/lib/flatpak/exports/bin/io.gitlab.librewolf-community",
-            ".var/app/io.gitlab.librewolf-community/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Librewolf",
-            ".local/share/flatpak/exports/bin/io.gitlab.librewolf-community",
-            ".local/share/flatpak/exports/bin/io.gitlab.librewolf-community",
-            ".var/app/io.gitlab.librewolf-community/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Waterfox",
-            "/var/lib/flatpak/exports/bin/net.waterfox.waterfox",
-            "/var/lib/flatpak/exports/bin/net.waterfox.waterfox",
-            ".var/app/net.waterfox.waterfox/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Waterfox",
-            ".local/share/flatpak/exports/bin/net.waterfox.waterfox",
-            ".local/share/flatpak/exports/bin/net.waterfox.waterfox",
-            ".var/app/net.waterfox.waterfox/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Vivaldi",
-            "/var/lib/flatpak/exports/bin/com.vivaldi.Vivaldi",
-            "/var/lib/flatpak/exports/bin/com.vivaldi.Vivaldi",
-            ".var/app/com.vivaldi.Vivaldi/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Vivaldi",
-            ".local/share/flatpak/exports/bin/com.vivaldi.Vivaldi",
-            ".local/share/flatpak/exports/bin/com.vivaldi.Vivaldi",
-            ".var/app/com.vivaldi.Vivaldi/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Ungoogled Chromium",
-            "/var/lib/flatpak/exports/bin/io.github.ungoogled_software.ungoogled_chromium",
-            "/var/lib/flatpak/exports/bin/io.github.ungoogled_software.ungoogled_chromium",
-            ".var/app/io.github.ungoogled_software.ungoogled_chromium/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Ungoogled Chromium",
-            ".local/share/flatpak/exports/bin/io.github.ungoogled_software.ungoogled_chromium",
-            ".local/share/flatpak/exports/bin/io.github.ungoogled_software.ungoogled_chromium",
-            ".var/app/io.github.ungoogled_software.ungoogled_chromium/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Chromium",
-            "/var/lib/flatpak/exports/bin/org.chromium.Chromium",
-            "/var/lib/flatpak/exports/bin/org.chromium.Chromium",
-            ".var/app/org.chromium.Chromium/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Chromium",
-            ".local/share/flatpak/exports/bin/org.chromium.Chromium",
-            ".local/share/flatpak/exports/bin/org.chromium.Chromium",
-            ".var/app/org.chromium.Chromium/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Microsoft Edge",
-            "/var/lib/flatpak/exports/bin/com.microsoft.Edge",
-            "/var/lib/flatpak/exports/bin/com.microsoft.Edge",
-            ".var/app/com.microsoft.Edge/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Microsoft Edge",
-            ".local/share/flatpak/exports/bin/com.microsoft.Edge",
-            ".local/share/flatpak/exports/bin/com.microsoft.Edge",
-            ".var/app/com.microsoft.Edge/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Brave",
-            "/var/lib/flatpak/exports/bin/com.brave.Browser",
-            "/var/lib/flatpak/exports/bin/com.brave.Browser",
-            ".var/app/com.brave.Browser/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Brave",
-            ".local/share/flatpak/exports/bin/com.brave.Browser",
-            ".local/share/flatpak/exports/bin/com.brave.Browser",
-            ".var/app/com.brave.Browser/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FalkonFlatpak,
-            "Falkon",
-            "/var/lib/flatpak/exports/bin/org.kde.falkon",
-            "/var/lib/flatpak/exports/bin/org.kde.falkon",
-            ".var/app/org.kde.falkon/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FalkonFlatpak,
-            "Falkon",
-            ".local/share/flatpak/exports/bin/org.kde.falkon",
-            ".local/share/flatpak/exports/bin/org.kde.falkon",
-            ".var/app/org.kde.falkon/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Yandex",
-            "/var/lib/flatpak/exports/bin/ru.yandex.Browser",
-            "/var/lib/flatpak/exports/bin/ru.yandex.Browser",
-            ".var/app/ru.yandex.Browser/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::ChromiumFlatpak,
-            "Yandex",
-            ".local/share/flatpak/exports/bin/ru.yandex.Browser",
-            ".local/share/flatpak/exports/bin/ru.yandex.Browser",
-            ".var/app/ru.yandex.Browser/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Floorp",
-            "/var/lib/flatpak/exports/bin/one.ablaze.floorp",
-            "/var/lib/flatpak/exports/bin/one.ablaze.floorp",
-            ".var/app/one.ablaze.floorp/data/profiles",
-        ),
-        Browser::new(
-            BrowserType::FirefoxFlatpak,
-            "Floorp",
-            ".local/share/flatpak/exports/bin/one.ablaze.floorp",
-            ".local/share/flatpak/exports/bin/one.ablaze.floorp",
-            ".var/app/one.ablaze.floorp/data/profiles",
-        ),
-    ]
+    let mut browsers = Vec::new();
+
+    for installation in flatpak_installations() {
+        let Ok(exports) = fs::read_dir(installation.join("exports/bin")) else {
+            continue;
+        };
+
+        let mut ids: Vec<String> = exports
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        ids.sort();
+
+        for id in ids {
+            let exec = installation.join("exports/bin").join(&id);
+
+            let (name, _type) = match KNOWN_FLATPAKS.iter().find(|(known, _, _)| *known == id) {
+                Some((_, name, _type)) => (name.to_string(), _type.clone()),
+                None => {
+                    let desktop_file = installation
+                        .join("exports/share/applications")
+                        .join(format!("{}.desktop", id));
+
+                    let Some(entry) = DesktopEntry::read(&desktop_file)
+                        .ok()
+                        .and_then(|entry| entry.group(DESKTOP_ENTRY_GROUP).cloned())
+                    else {
+                        continue;
+                    };
+
+                    let handles_web = entry
+                        .get_list("MimeType")
+                        .iter()
+                        .any(|mime| BROWSER_MIME_TYPES.contains(&mime.as_str()));
+                    let name = entry.get("Name").unwrap_or(id.clone());
+                    let family = match GeckoApp::find_flatpak(&installation, &id) {
+                        Some(_) => "firefox",
+                        None => family_of(&format!("{} {}", id, name)),
+                    };
+
+                    match browser_type(family, true) {
+                        Some(_type) if handles_web => (name, _type),
+                        _ => continue,
+                    }
+                }
+            };
+
+            let exec = exec.to_string_lossy();
+            let profile_root = format!(".var/app/{}/data/profiles", id);
+
+            browsers.push(with_overrides(
+                Browser::new(_type, &name, &exec, &exec, &profile_root),
+                &id,
+            ));
+        }
+    }
+
+    browsers
 }
 
-pub fn nix_browsers() -> Vec<Browser> {
-    vec![
-        Browser::new(
-            BrowserType::Firefox,
-            "Firefox",
-            "firefox",
-            "/run/current-system/sw/bin/firefox",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Floorp",
-            "floorp",
-            "/run/current-system/sw/bin/floorp",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Brave",
-            "brave",
-            "/run/current-system/sw/bin/brave",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chrome",
-            "google-chrome-stable",
-            "/run/current-system/sw/bin/google-chrome-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Chromium",
-            "chromium",
-            "/run/current-system/sw/bin/chromium",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Firefox,
-            "Librewolf",
-            "librewolf",
-            "/run/current-system/sw/bin/librewolf",
-            ".local/share/quick-webapps/firefox",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Vivaldi",
-            "vivaldi-stable",
-            "/run/current-system/sw/bin/vivaldi",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge",
-            "microsoft-edge-stable",
-            "/run/current-system/sw/bin/microsoft-edge-stable",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge Beta",
-            "microsoft-edge-beta",
-            "/run/current-system/sw/bin/microsoft-edge-beta",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Microsoft Edge Dev",
-            "microsoft-edge-dev",
-            "/run/current-system/sw/bin/microsoft-edge-dev",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Chromium,
-            "Yandex",
-            "yandex-browser",
-            "/run/current-system/sw/bin/yandex-browser",
-            ".local/share/quick-webapps/chromium",
-        ),
-        Browser::new(
-            BrowserType::Falkon,
-            "Falkon",
-            "falkon",
-            "/run/current-system/sw/bin/falkon",
-            ".local/share/quick-webapps/falkon",
-        ),
-    ]
+const CONFIG_FILE: &str = "browsers.toml";
+
+#[derive(Debug, Deserialize)]
+struct BrowsersConfig {
+    #[serde(default, rename = "browser")]
+    browsers: Vec<ConfigBrowser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfigBrowser {
+    name: String,
+    family: BrowserType,
+    exec: String,
+    test: Option<String>,
+    profile: Option<String>,
+    template: Option<String>,
+    private_flag: Option<String>,
+}
+
+impl ConfigBrowser {
+    fn into_browser(self) -> Option<Browser> {
+        if self.family == BrowserType::NoBrowser {
+            tracing::warn!("{}: {:?} is not a browser family", self.name, self.family);
+            return None;
+        }
+
+        let test = match &self.test {
+            Some(test) => paths::resolve(test),
+            None if self.exec.contains('/') => paths::resolve(&self.exec),
+            None => which(&self.exec)?,
+        };
+
+        if !test.exists() {
+            tracing::debug!("{}: {} not found", self.name, test.display());
+            return None;
+        }
+
+        let profile_root = self
+            .profile
+            .unwrap_or_else(|| native_profile_root(&self.family, &test));
+
+        let browser = Browser::new(
+            self.family,
+            &self.name,
+            &self.exec,
+            &test.to_string_lossy(),
+            &profile_root,
+        );
+
+        Some(browser.with_flags(self.template.as_deref(), self.private_flag.as_deref()))
+    }
+}
+
+fn config_path() -> PathBuf {
+    paths::app_config_dir().join(CONFIG_FILE)
+}
+
+/// Browsers declared in `browsers.toml`, for builds we do not detect:
+///
+/// ```toml
+/// [[browser]]
+/// name = "Corp Chromium"
+/// family = "Chromium"
+/// exec = "/opt/corp/chromium/chrome"
+/// test = "/opt/corp/chromium/chrome"
+/// profile = ".local/share/quick-webapps/corp-chromium"
+/// private_flag = "--incognito"
+/// ```
+///
+/// `family` is one of the `BrowserType` variants. `test` defaults to the
+/// executable and `profile` to the profile root of the family, while
+/// `template` and `private_flag` replace the command line of the family. Entries
+/// whose test path does not exist are left out, like any browser that is
+/// not installed.
+pub fn config_browsers() -> Vec<Browser> {
+    let path = config_path();
+
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    match toml::from_str::<BrowsersConfig>(&content) {
+        Ok(config) => config
+            .browsers
+            .into_iter()
+            .filter_map(ConfigBrowser::into_browser)
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Cannot read {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
 }
@@ -0,0 +1,330 @@
+use std::{fmt, fs};
+
+use reqwest::Client;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::paths;
+
+const MENUS_CSS: &str = include_str!("../data/runtime/firefox/profile/chrome/menus.css");
+const MINIMAL_CSS: &str =
+    include_str!("../data/runtime/firefox/profile/chrome/layouts/minimal.css");
+const COMPACT_CSS: &str =
+    include_str!("../data/runtime/firefox/profile/chrome/layouts/compact.css");
+const TABS_CSS: &str = include_str!("../data/runtime/firefox/profile/chrome/layouts/tabs.css");
+
+/// Firefox window layout used while the navigation bar is off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Nothing but the page.
+    #[default]
+    Minimal,
+    /// Back, forward and reload buttons.
+    Compact,
+    /// A tab strip, links opening in new tabs.
+    Tabs,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 3] = [Layout::Minimal, Layout::Compact, Layout::Tabs];
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.as_str() == text.trim().to_lowercase())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::Minimal => "minimal",
+            Layout::Compact => "compact",
+            Layout::Tabs => "tabs",
+        }
+    }
+
+    fn css(&self) -> &'static str {
+        match self {
+            Layout::Minimal => MINIMAL_CSS,
+            Layout::Compact => COMPACT_CSS,
+            Layout::Tabs => TABS_CSS,
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    /// Reads `#rgb` and `#rrggbb`, the forms sites use for `theme_color`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let hex = text.trim().strip_prefix('#')?;
+
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return None,
+        };
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        Some(Self {
+            red: channel(0)?,
+            green: channel(2)?,
+            blue: channel(4)?,
+        })
+    }
+
+    fn from_floats(red: f32, green: f32, blue: f32) -> Self {
+        let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+
+        Self {
+            red: channel(red),
+            green: channel(green),
+            blue: channel(blue),
+        }
+    }
+
+    fn is_light(&self) -> bool {
+        let luminance =
+            0.299 * self.red as f32 + 0.587 * self.green as f32 + 0.114 * self.blue as f32;
+
+        luminance > 150.
+    }
+
+    /// Black or white, whichever reads better on this color.
+    fn text(&self) -> Self {
+        match self.is_light() {
+            true => Self::from_floats(0., 0., 0.),
+            false => Self::from_floats(1., 1., 1.),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// Colors of the window around the page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tint {
+    #[default]
+    None,
+    /// Accent and background of the current COSMIC theme, read again each
+    /// time the profile is written.
+    Cosmic,
+    /// A fixed color, usually the site's `theme_color`.
+    Color(Color),
+}
+
+impl Tint {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "" | "none" => Some(Tint::None),
+            "cosmic" => Some(Tint::Cosmic),
+            color => Color::parse(color).map(Tint::Color),
+        }
+    }
+
+    /// Background and accent to paint with, if any.
+    fn colors(&self) -> Option<(Color, Color)> {
+        match self {
+            Tint::None => None,
+            Tint::Cosmic => cosmic_colors(),
+            Tint::Color(color) => Some((*color, *color)),
+        }
+    }
+}
+
+impl fmt::Display for Tint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tint::None => write!(f, "none"),
+            Tint::Cosmic => write!(f, "cosmic"),
+            Tint::Color(color) => write!(f, "{}", color),
+        }
+    }
+}
+
+// the first `base: (red: .., green: .., blue: .., alpha: ..)` of a theme
+// component, as cosmic-config writes it
+fn ron_base_color(content: &str) -> Option<Color> {
+    let start = content.find("base:")?;
+    let rest = &content[start..];
+    let fields = &rest[rest.find('(')? + 1..rest.find(')')?];
+
+    let channel = |name: &str| {
+        fields.split(',').find_map(|field| {
+            let (key, value) = field.split_once(':')?;
+            (key.trim() == name).then(|| value.trim().parse::<f32>().ok())?
+        })
+    };
+
+    Some(Color::from_floats(
+        channel("red")?,
+        channel("green")?,
+        channel("blue")?,
+    ))
+}
+
+/// Background and accent of the COSMIC theme in use, `None` outside COSMIC.
+pub fn cosmic_colors() -> Option<(Color, Color)> {
+    let config = paths::host_config_home().join("cosmic");
+
+    let is_dark = fs::read_to_string(config.join("com.system76.CosmicTheme.Mode/v1/is_dark"))
+        .map(|content| content.trim() != "false")
+        .unwrap_or(true);
+    let theme = config.join(match is_dark {
+        true => "com.system76.CosmicTheme.Dark/v1",
+        false => "com.system76.CosmicTheme.Light/v1",
+    });
+
+    let read = |name: &str| ron_base_color(&fs::read_to_string(theme.join(name)).ok()?);
+
+    Some((read("background")?, read("accent")?))
+}
+
+fn tint_css(background: Color, accent: Color) -> String {
+    let text = background.text();
+    let accent_text = accent.text();
+
+    format!(
+        ":root {{
+	--toolbar-bgcolor: {background} !important;
+	--toolbar-color: {text} !important;
+	--lwt-accent-color: {background} !important;
+	--lwt-text-color: {text} !important;
+	--tab-selected-bgcolor: {accent} !important;
+	--tab-selected-textcolor: {accent_text} !important;
+	--focus-outline-color: {accent} !important;
+}}
+
+#navigator-toolbox, #TabsToolbar, #nav-bar {{
+	background-color: {background} !important;
+	color: {text} !important;
+}}
+"
+    )
+}
+
+/// `userChrome.css` for a web app. With the navigation bar on, Firefox keeps
+/// its own toolbars and only the tint is applied.
+pub fn user_chrome_css(layout: Layout, navbar: bool, tint: Tint) -> String {
+    let mut css = String::new();
+
+    if !navbar {
+        css.push_str(layout.css());
+        css.push('\n');
+        css.push_str(MENUS_CSS);
+    }
+
+    if let Some((background, accent)) = tint.colors() {
+        css.push('\n');
+        css.push_str(&tint_css(background, accent));
+    }
+
+    css
+}
+
+/// `theme_color` from the site's web app manifest, falling back to its
+/// `theme-color` meta tag.
+pub async fn site_theme_color(url: &str) -> Option<Color> {
+    let base = Url::parse(url).ok()?;
+    let client = Client::new();
+    let content = client.get(url).send().await.ok()?.text().await.ok()?;
+
+    let (manifest, meta) = {
+        let document = Html::parse_document(&content);
+        let link = Selector::parse("link[rel=\"manifest\"]").unwrap();
+        let meta = Selector::parse("meta[name=\"theme-color\"]").unwrap();
+
+        (
+            document
+                .select(&link)
+                .find_map(|link| link.value().attr("href"))
+                .and_then(|href| base.join(href).ok()),
+            document
+                .select(&meta)
+                .find_map(|meta| meta.value().attr("content"))
+                .and_then(Color::parse),
+        )
+    };
+
+    if let Some(manifest) = manifest {
+        let color = async {
+            let manifest = client.get(manifest).send().await.ok()?.text().await.ok()?;
+            let manifest: serde_json::Value = serde_json::from_str(&manifest).ok()?;
+
+            Color::parse(manifest.get("theme_color")?.as_str()?)
+        };
+
+        if let Some(color) = color.await {
+            return Some(color);
+        }
+    }
+
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(
+            Color::parse("#1A73e8"),
+            Some(Color {
+                red: 0x1a,
+                green: 0x73,
+                blue: 0xe8
+            })
+        );
+        assert_eq!(
+            Color::parse("#fff").map(|c| c.to_string()),
+            Some(String::from("#ffffff"))
+        );
+        assert_eq!(Color::parse("rebeccapurple"), None);
+        assert_eq!(Tint::parse("cosmic"), Some(Tint::Cosmic));
+        assert_eq!(Tint::parse("#000").unwrap().to_string(), "#000000");
+    }
+
+    #[test]
+    fn reads_cosmic_component() {
+        let accent = "(
+    base: (
+        red: 0.3882353,
+        green: 0.81568627,
+        blue: 0.8745098,
+        alpha: 1.0,
+    ),
+    hover: (
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+        alpha: 1.0,
+    ),
+)";
+
+        assert_eq!(ron_base_color(accent).unwrap().to_string(), "#63d0df");
+    }
+
+    #[test]
+    fn navbar_keeps_toolbars_and_the_tint() {
+        let tint = Tint::Color(Color::parse("#202020").unwrap());
+
+        assert_eq!(user_chrome_css(Layout::Tabs, true, Tint::None), "");
+        assert!(user_chrome_css(Layout::Tabs, true, tint).contains("--toolbar-bgcolor: #202020"));
+        assert!(user_chrome_css(Layout::Compact, false, Tint::None).contains("#back-button"));
+    }
+}
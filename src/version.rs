@@ -0,0 +1,488 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, create_dir_all},
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{split_exec, write_atomic, Browser},
+    gecko::GeckoApp,
+    paths,
+};
+
+// browsers that ignore --version may open a window instead of exiting
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const CACHE_FILE: &str = "browser-versions.json";
+
+// Flags older releases do not understand and that are left out of their
+// command lines: (engine family, flag, first version that has it). The
+// Firefox and Chromium flags of the templates are older than any release
+// still around, only Falkon learned --wmclass late.
+const FLAGS_SINCE: &[(&str, &str, &[u32])] = &[("falkon", "--wmclass", &[3, 1])];
+
+// user.js prefs that were renamed or dropped: (pref, first version that
+// reads it, first version that no longer does), empty when unbounded
+const PREFS_BETWEEN: &[(&str, &[u32], &[u32])] = &[
+    ("browser.tabs.drawInTitlebar", &[], &[96]),
+    ("browser.tabs.inTitlebar", &[96], &[]),
+    ("browser.translations.automaticallyPopup", &[118], &[]),
+    ("plugin.state.flash", &[], &[85]),
+    (
+        "toolkit.legacyUserProfileCustomizations.stylesheets",
+        &[68],
+        &[],
+    ),
+];
+
+// Options that only work from some release on: (engine family, option,
+// first version that supports it). GNOME Web before 43 names web app
+// profiles with a dash and does not run ours in application mode.
+const OPTIONS_SINCE: &[(&str, WebAppOption, &[u32])] =
+    &[("epiphany", WebAppOption::IsolatedProfile, &[43])];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebAppOption {
+    IsolatedProfile,
+}
+
+impl fmt::Display for WebAppOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebAppOption::IsolatedProfile => write!(f, "isolated profiles"),
+        }
+    }
+}
+
+/// Numeric part of a browser version, e.g. `128.0.3` for `128.0.3esr`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub Vec<u32>);
+
+impl Version {
+    /// Takes the first word that starts with a digit, so the output of
+    /// `--version` can be passed as it is.
+    pub fn parse(text: &str) -> Option<Self> {
+        let word = text
+            .split_whitespace()
+            .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+
+        let parts: Vec<u32> = word
+            .split('.')
+            .map_while(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok()
+            })
+            .collect();
+
+        (!parts.is_empty()).then_some(Self(parts))
+    }
+
+    pub fn at_least(&self, other: &[u32]) -> bool {
+        self.0.as_slice() >= other
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(u32::to_string).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedVersion {
+    modified: u64,
+    version: Option<String>,
+}
+
+// keyed by executable; an entry is good while the binary is not replaced
+static CACHE: Lazy<Mutex<HashMap<String, CachedVersion>>> = Lazy::new(|| {
+    let cache = fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    Mutex::new(cache)
+});
+
+fn cache_path() -> PathBuf {
+    paths::app_cache_dir().join(CACHE_FILE)
+}
+
+fn save_cache(cache: &HashMap<String, CachedVersion>) {
+    let path = cache_path();
+
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent);
+    }
+
+    match serde_json::to_vec_pretty(cache) {
+        Ok(content) => {
+            if let Err(e) = write_atomic(&path, &content) {
+                tracing::warn!("Cannot write {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => tracing::warn!("Cannot store browser versions: {}", e),
+    }
+}
+
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+fn run_version(exec: &str) -> Option<String> {
+    let mut child = Command::new(exec)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    let mut output = String::new();
+    child.stdout.take()?.read_to_string(&mut output).ok()?;
+
+    Some(output)
+}
+
+fn metainfo_version(files: &Path, id: &str) -> Option<Version> {
+    ["share/metainfo", "share/appdata"]
+        .iter()
+        .flat_map(|dir| {
+            [
+                files.join(dir).join(format!("{}.metainfo.xml", id)),
+                files.join(dir).join(format!("{}.appdata.xml", id)),
+            ]
+        })
+        .find_map(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            let release = content.split("<release ").nth(1)?;
+            let version = release.split("version=\"").nth(1)?.split('"').next()?;
+
+            Version::parse(version)
+        })
+}
+
+// running a Flatpak just to ask for its version is slow, the deployed
+// files tell as much
+fn flatpak_version(exec: &Path) -> Option<Version> {
+    let id = exec.file_name()?.to_str()?;
+    let installation = exec.parent()?.parent()?.parent()?;
+    let files = installation
+        .join("app")
+        .join(id)
+        .join("current/active/files");
+
+    GeckoApp::find_flatpak(installation, id)
+        .and_then(|app| app.engine_version())
+        .or_else(|| metainfo_version(&files, id))
+}
+
+fn snap_version(snap: &str) -> Option<Version> {
+    ["/snap", "/var/lib/snapd/snap"].iter().find_map(|root| {
+        let meta =
+            fs::read_to_string(Path::new(root).join(snap).join("current/meta/snap.yaml")).ok()?;
+
+        meta.lines()
+            .find_map(|line| line.strip_prefix("version:"))
+            .and_then(|version| Version::parse(version.trim().trim_matches(['\'', '"'])))
+    })
+}
+
+fn probe(browser: &Browser) -> Option<Version> {
+    let exec = Path::new(&browser.exec);
+
+    if let Some(snap) = browser.snap_name() {
+        return snap_version(&snap);
+    }
+
+    if browser.exec.contains("/exports/bin/") {
+        return flatpak_version(exec);
+    }
+
+    if browser._type.family() == "firefox" {
+        if let Some(version) = GeckoApp::find(exec).and_then(|app| app.engine_version()) {
+            return Some(version);
+        }
+    }
+
+    run_version(&browser.exec).and_then(|output| Version::parse(&output))
+}
+
+/// Installed version of a browser as far as the cache knows, without
+/// running anything. `probe_versions` fills the cache.
+pub fn cached_version(browser: &Browser) -> Option<Version> {
+    let cache = CACHE.lock().unwrap();
+    let cached = cache.get(&browser.exec)?;
+
+    (cached.modified == modified(&browser.test))
+        .then(|| cached.version.as_deref().and_then(Version::parse))
+        .flatten()
+}
+
+/// Probes the browsers the cache does not know yet, once per binary, and
+/// returns whether anything was learned. This may start browsers and wait
+/// for them, so the window runs it in a background task.
+pub fn probe_versions(browsers: &[Browser]) -> bool {
+    let mut probed = HashMap::new();
+
+    for browser in browsers.iter().filter(|browser| browser.is_installed()) {
+        let modified = modified(&browser.test);
+        let known = CACHE
+            .lock()
+            .unwrap()
+            .get(&browser.exec)
+            .is_some_and(|cached| cached.modified == modified);
+
+        if known || probed.contains_key(&browser.exec) {
+            continue;
+        }
+
+        let version = probe(browser);
+        tracing::debug!("{} version: {:?}", browser.name, version);
+
+        probed.insert(
+            browser.exec.clone(),
+            CachedVersion {
+                modified,
+                version: version.as_ref().map(Version::to_string),
+            },
+        );
+    }
+
+    if probed.is_empty() {
+        return false;
+    }
+
+    let mut cache = CACHE.lock().unwrap();
+    cache.extend(probed);
+    save_cache(&cache);
+
+    true
+}
+
+// words of an Exec value as they are written, quotes and all
+fn exec_words(command: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in command.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ' ' if !in_quotes => {
+                if let Some(start) = start.take() {
+                    words.push(&command[start..i]);
+                }
+                continue;
+            }
+            _ => (),
+        }
+
+        start.get_or_insert(i);
+    }
+
+    if let Some(start) = start {
+        words.push(&command[start..]);
+    }
+
+    words
+}
+
+/// Drops flags the installed release does not know from a command line,
+/// along with their value when it is a separate word. Everything else,
+/// `%` field codes and quoting included, is kept as it is.
+pub fn strip_unknown_flags(command: &str, family: &str, version: Option<&Version>) -> String {
+    let Some(version) = version else {
+        return command.to_string();
+    };
+
+    let unknown: Vec<&str> = FLAGS_SINCE
+        .iter()
+        .filter(|(f, _, since)| *f == family && !version.at_least(since))
+        .map(|(_, flag, _)| *flag)
+        .collect();
+
+    if unknown.is_empty() {
+        return command.to_string();
+    }
+
+    let mut words = Vec::new();
+    let mut removed = false;
+    let mut skip_value = false;
+
+    for raw in exec_words(command) {
+        let word = split_exec(raw).concat();
+
+        if std::mem::take(&mut skip_value) && !word.starts_with('-') {
+            continue;
+        }
+
+        match unknown.iter().find(|flag| word.starts_with(**flag)) {
+            Some(flag) => {
+                removed = true;
+                skip_value = word == *flag;
+            }
+            None => words.push(raw),
+        }
+    }
+
+    match removed {
+        true => words.join(" "),
+        false => command.to_string(),
+    }
+}
+
+/// Whether a `user.js` pref is read by the given Firefox release.
+pub fn pref_supported(pref: &str, version: Option<&Version>) -> bool {
+    let Some(version) = version else {
+        return true;
+    };
+
+    PREFS_BETWEEN
+        .iter()
+        .filter(|(name, _, _)| *name == pref)
+        .all(|(_, since, until)| {
+            version.at_least(since) && (until.is_empty() || !version.at_least(until))
+        })
+}
+
+/// Options an app uses that the installed browser is too old for.
+pub fn outdated_options(browser: &Browser, isolated_profile: bool) -> Vec<(WebAppOption, Version)> {
+    let Some(version) = browser.version.as_ref() else {
+        return Vec::new();
+    };
+
+    let used = |option: &WebAppOption| match option {
+        WebAppOption::IsolatedProfile => isolated_profile,
+    };
+
+    OPTIONS_SINCE
+        .iter()
+        .filter(|(family, option, since)| {
+            *family == browser._type.family() && used(option) && !version.at_least(since)
+        })
+        .map(|(_, option, since)| (*option, Version(since.to_vec())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::BrowserType;
+
+    use super::*;
+
+    #[test]
+    fn parses_version_output() {
+        let outputs = [
+            ("Mozilla Firefox 128.0.3esr\n", vec![128, 0, 3]),
+            ("Mozilla Firefox 129.0b3\n", vec![129, 0]),
+            ("Mozilla LibreWolf 127.0.2-2\n", vec![127, 0, 2]),
+            (
+                "Chromium 126.0.6478.126 built on Debian 12.6, running on Debian 12.6\n",
+                vec![126, 0, 6478, 126],
+            ),
+            ("Google Chrome 126.0.6478.126 \n", vec![126, 0, 6478, 126]),
+            ("Microsoft Edge 126.0.2592.87 \n", vec![126, 0, 2592, 87]),
+            ("Brave Browser 126.1.67.119 \n", vec![126, 1, 67, 119]),
+            ("Falkon 23.08.5\n", vec![23, 8, 5]),
+            ("Web 46.1\n", vec![46, 1]),
+        ];
+
+        for (output, expected) in outputs {
+            assert_eq!(
+                Version::parse(output),
+                Some(Version(expected)),
+                "{}",
+                output
+            );
+        }
+
+        assert_eq!(Version::parse("Falkon\n"), None);
+    }
+
+    #[test]
+    fn drops_flags_old_releases_lack() {
+        let command = "falkon --wmclass WebApp-mail --portable --profile /p --current-tab https://mail.example.com";
+
+        assert_eq!(
+            strip_unknown_flags(command, "falkon", Version::parse("3.0.1").as_ref()),
+            "falkon --portable --profile /p --current-tab https://mail.example.com"
+        );
+        assert_eq!(
+            strip_unknown_flags(command, "falkon", Version::parse("23.08.5").as_ref()),
+            command
+        );
+        assert_eq!(strip_unknown_flags(command, "falkon", None), command);
+
+        // what the user wrote stays untouched
+        let command = r#"falkon --wmclass WebApp-mail --profile "/my profiles/p" -o %u https://mail.example.com/?a=50%%"#;
+
+        assert_eq!(
+            strip_unknown_flags(command, "falkon", Version::parse("3.0.1").as_ref()),
+            r#"falkon --profile "/my profiles/p" -o %u https://mail.example.com/?a=50%%"#
+        );
+        assert_eq!(
+            strip_unknown_flags(command, "firefox", Version::parse("3.0.1").as_ref()),
+            command
+        );
+    }
+
+    #[test]
+    fn picks_prefs_by_release() {
+        let old = Version::parse("91.0");
+        let new = Version::parse("128.0");
+
+        assert!(pref_supported("browser.tabs.drawInTitlebar", old.as_ref()));
+        assert!(!pref_supported("browser.tabs.drawInTitlebar", new.as_ref()));
+        assert!(!pref_supported("browser.tabs.inTitlebar", old.as_ref()));
+        assert!(pref_supported("browser.tabs.inTitlebar", new.as_ref()));
+        assert!(pref_supported("browser.cache.disk.enable", old.as_ref()));
+        assert!(pref_supported("browser.tabs.inTitlebar", None));
+    }
+
+    #[test]
+    fn reports_outdated_options() {
+        let mut browser = Browser::new(BrowserType::Epiphany, "Web", "epiphany", "", "profiles");
+        browser.version = Version::parse("42.4");
+
+        assert_eq!(
+            outdated_options(&browser, true),
+            vec![(WebAppOption::IsolatedProfile, Version(vec![43]))]
+        );
+        assert!(outdated_options(&browser, false).is_empty());
+
+        browser.version = Version::parse("46.1");
+        assert!(outdated_options(&browser, true).is_empty());
+    }
+}
@@ -9,6 +9,7 @@ pub enum WarnMessages {
     AppUrl,
     AppIcon,
     AppBrowser,
+    OutdatedBrowser,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +30,9 @@ impl std::fmt::Display for WarnMessages {
             WarnMessages::AppBrowser => {
                 write!(f, "{}", fl!("warning", "app-browser"))
             }
+            WarnMessages::OutdatedBrowser => {
+                write!(f, "{}", fl!("warning", "outdated-browser"))
+            }
         }
     }
 }